@@ -0,0 +1,296 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{quote, quote_spanned};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, Fields, Ident, LitStr, Token, parse_macro_input};
+
+/// generates `environs::FromEnv` for a struct with named fields
+///
+/// each field resolves like an `env!` call; `#[env(...)]` takes the same keys, fallbacks
+/// (`default`, `default_str`, `default_fn`, `resolve_with`, `parsed`) and `Var` modifiers such as
+/// `sensitive`. fields without keys use their name in upper case as the only key.
+#[proc_macro_derive(FromEnv, attributes(env))]
+pub fn derive_from_env(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_env(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// `Var` methods a field may apply before its fallback
+const VAR_MODIFIERS: &[&str] = &[
+    "prefix",
+    "or_keys",
+    "scope",
+    "sensitive",
+    "schemes",
+    "deprecated",
+    "deny_deprecated",
+    "range",
+    "one_of",
+    "matches",
+    "non_empty",
+    "validate",
+    "empty_as",
+    "normalize_keys",
+];
+
+enum Fallback {
+    None,
+    Default(Expr),
+    DefaultStr(LitStr),
+    DefaultFn(Expr),
+    ResolveWith(Expr),
+    Parsed,
+}
+
+struct FieldAttr {
+    keys: Vec<LitStr>,
+    fallback: Fallback,
+    modifiers: Vec<(Ident, Option<Expr>)>,
+}
+
+enum AttrArg {
+    Key(LitStr),
+    Option(Ident, Option<Expr>),
+}
+
+impl Parse for AttrArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            return Ok(Self::Key(input.parse()?));
+        }
+        let name: Ident = input.parse()?;
+        if input.parse::<Option<Token![=]>>()?.is_none() {
+            return Ok(Self::Option(name, None));
+        }
+        Ok(Self::Option(name, Some(input.parse()?)))
+    }
+}
+
+impl FieldAttr {
+    fn from_field(field: &syn::Field) -> syn::Result<Self> {
+        let mut attr = Self {
+            keys: Vec::new(),
+            fallback: Fallback::None,
+            modifiers: Vec::new(),
+        };
+        for env_attr in field.attrs.iter().filter(|attr| attr.path().is_ident("env")) {
+            let args = env_attr.parse_args_with(Punctuated::<AttrArg, Token![,]>::parse_terminated)?;
+            for arg in args {
+                match arg {
+                    AttrArg::Key(key) => attr.keys.push(key),
+                    AttrArg::Option(name, value) => attr.set_option(name, value)?,
+                }
+            }
+        }
+        if attr.keys.is_empty() {
+            let ident = field
+                .ident
+                .as_ref()
+                .ok_or_else(|| syn::Error::new(field.span(), "FromEnv requires named fields"))?;
+            let name = ident.to_string();
+            let key = name.strip_prefix("r#").unwrap_or(&name).to_uppercase();
+            attr.keys.push(LitStr::new(&key, ident.span()));
+        }
+        Ok(attr)
+    }
+
+    fn set_option(&mut self, name: Ident, value: Option<Expr>) -> syn::Result<()> {
+        let is_fallback = matches!(name.to_string().as_str(), "default" | "default_str" | "default_fn" | "resolve_with" | "parsed");
+        if !is_fallback {
+            if !VAR_MODIFIERS.contains(&name.to_string().as_str()) {
+                return Err(syn::Error::new(name.span(), format!("unknown env option `{name}`")));
+            }
+            self.modifiers.push((name, value));
+            return Ok(());
+        }
+        if !matches!(self.fallback, Fallback::None) {
+            return Err(syn::Error::new(name.span(), "only one of default, default_str, default_fn, resolve_with or parsed is allowed"));
+        }
+        if name == "parsed" {
+            if let Some(value) = value {
+                return Err(syn::Error::new(value.span(), "`parsed` takes no value"));
+            }
+            self.fallback = Fallback::Parsed;
+            return Ok(());
+        }
+        let Some(value) = value else {
+            return Err(syn::Error::new(name.span(), format!("`{name}` expects a value")));
+        };
+        self.fallback = match name.to_string().as_str() {
+            "default" => Fallback::Default(value),
+            "default_str" => match value {
+                Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) => Fallback::DefaultStr(lit),
+                other => return Err(syn::Error::new(other.span(), "default_str expects a string literal")),
+            },
+            "default_fn" => Fallback::DefaultFn(value),
+            _ => Fallback::ResolveWith(value),
+        };
+        Ok(())
+    }
+}
+
+fn expand_from_env(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new(Span::call_site(), "FromEnv can only be derived for structs with named fields")),
+        },
+        _ => return Err(syn::Error::new(Span::call_site(), "FromEnv can only be derived for structs")),
+    };
+
+    let mut inits = Vec::with_capacity(fields.len());
+    for field in fields {
+        let attr = FieldAttr::from_field(field)?;
+        let ident = &field.ident;
+        let keys = &attr.keys;
+        let modifiers = attr.modifiers.iter().map(|(name, value)| quote! { .#name(#value) });
+        let var = quote! { ::environs::Var::from_source(source, &[#(#keys),*]) #(#modifiers)* };
+        let lookup = match attr.fallback {
+            Fallback::None => quote! { #var.get() },
+            Fallback::Default(expr) => quote! { #var.default(#expr).get() },
+            Fallback::DefaultStr(lit) => quote! { #var.default_str(#lit).get() },
+            Fallback::DefaultFn(expr) => quote! { #var.default_fn(#expr).get() },
+            Fallback::ResolveWith(expr) => quote! { #var.resolve_with(#expr) },
+            Fallback::Parsed => quote! { #var.parsed() },
+        };
+        let span = field.span();
+        inits.push(quote_spanned! {span=>
+            #ident: #lookup.map_err(|err| err.with_location(file!(), line!()))?
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::environs::FromEnv for #name #ty_generics #where_clause {
+            fn from_source(source: &dyn ::environs::Source) -> ::environs::Result<Self> {
+                Ok(Self { #(#inits),* })
+            }
+        }
+    })
+}
+
+/// generates `environs::FromEnvStr` for an enum whose variants have no fields
+///
+/// variants match case-insensitively by their name in snake case (`CompactJson` is
+/// `compact_json`) or as written (`compactjson`), with surrounding whitespace trimmed.
+/// `#[env(rename = "...")]` replaces both and `#[env(alias = "...")]` accepts another.
+/// `type_name` is the enum's name and a miss lists every variant.
+#[proc_macro_derive(FromEnvStr, attributes(env))]
+pub fn derive_from_env_str(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_env_str(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct VariantAttr {
+    name: String,
+    aliases: Vec<String>,
+}
+
+impl VariantAttr {
+    fn from_variant(variant: &syn::Variant) -> syn::Result<Self> {
+        let ident = variant.ident.to_string();
+        let ident = ident.strip_prefix("r#").unwrap_or(&ident);
+        let mut attr = Self {
+            name: snake_case(ident),
+            aliases: Vec::new(),
+        };
+        let mut renamed = false;
+        for env_attr in variant.attrs.iter().filter(|attr| attr.path().is_ident("env")) {
+            let args = env_attr.parse_args_with(Punctuated::<AttrArg, Token![,]>::parse_terminated)?;
+            for arg in args {
+                let (name, value) = match arg {
+                    AttrArg::Key(key) => return Err(syn::Error::new(key.span(), "expected `rename = \"...\"` or `alias = \"...\"`")),
+                    AttrArg::Option(name, value) => (name, value),
+                };
+                let value = match value {
+                    Some(Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. })) => lit.value(),
+                    _ => return Err(syn::Error::new(name.span(), format!("`{name}` expects a string literal"))),
+                };
+                match name.to_string().as_str() {
+                    "rename" => {
+                        attr.name = value;
+                        renamed = true;
+                    }
+                    "alias" => attr.aliases.push(value),
+                    _ => return Err(syn::Error::new(name.span(), format!("unknown option `{name}`, expected `rename` or `alias`"))),
+                }
+            }
+        }
+        // the ident itself (`CompactJson`) matches too, unless the variant was renamed
+        let spelled = |name: &String| name.eq_ignore_ascii_case(ident);
+        if !renamed && !spelled(&attr.name) && !attr.aliases.iter().any(spelled) {
+            attr.aliases.push(ident.to_owned());
+        }
+        Ok(attr)
+    }
+}
+
+/// `CompactJson` as `compact_json`, keeping acronyms together (`HTTPServer` as `http_server`)
+fn snake_case(ident: &str) -> String {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut snake = String::with_capacity(ident.len() + 4);
+    for (index, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() && index > 0 {
+            let prev = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_is_lower) {
+                snake.push('_');
+            }
+        }
+        snake.extend(ch.to_lowercase());
+    }
+    snake
+}
+
+fn expand_from_env_str(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(Span::call_site(), "FromEnvStr can only be derived for enums"));
+    };
+
+    let mut seen: Vec<String> = Vec::new();
+    let mut names = Vec::with_capacity(data.variants.len());
+    let mut arms = Vec::with_capacity(data.variants.len());
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new(variant.span(), "FromEnvStr can only be derived for enums whose variants have no fields"));
+        }
+        let attr = VariantAttr::from_variant(variant)?;
+        let mut patterns = Vec::with_capacity(attr.aliases.len() + 1);
+        for name in std::iter::once(&attr.name).chain(&attr.aliases) {
+            let lower = name.to_lowercase();
+            if seen.contains(&lower) {
+                return Err(syn::Error::new(variant.span(), format!("`{name}` names more than one variant")));
+            }
+            seen.push(lower.clone());
+            patterns.push(lower);
+        }
+        let ident = &variant.ident;
+        arms.push(quote! { #(#patterns)|* => Ok(Self::#ident), });
+        names.push(attr.name);
+    }
+
+    let name = &input.ident;
+    let type_name = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::environs::FromEnvStr for #name #ty_generics #where_clause {
+            type Err = ::environs::EnumParseError;
+
+            fn from_env_str(value: &str) -> ::std::result::Result<Self, Self::Err> {
+                match value.trim().to_lowercase().as_str() {
+                    #(#arms)*
+                    _ => Err(::environs::EnumParseError::new(value, #type_name, &[#(#names),*])),
+                }
+            }
+
+            fn type_name() -> &'static str {
+                #type_name
+            }
+        }
+    })
+}
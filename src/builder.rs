@@ -1,80 +1,518 @@
-use std::marker::PhantomData;
+use std::borrow::Cow;
+use std::fmt::{Debug, Display};
+use std::ops::RangeBounds;
 
-use crate::parse::FromEnvStr;
-use crate::resolve::{resolve, resolve_or, resolve_or_else, resolve_or_parse, resolve_with};
+use crate::empty::{EmptyAs, WithEmptyAs, get_checked, get_raw};
+use crate::error::{Error, Location};
+use crate::net::{check_scheme, mask_url_passwords};
+use crate::normalize::match_keys;
+use crate::parse::{FromEnvStr, Parsed};
+use crate::resolve::{Resolved, resolve_from, resolve_or_else_from, resolve_or_from, resolve_or_parse_from, resolve_traced_from, resolve_with_from};
+use crate::scope::{Scope, expand_keys};
+use crate::source::{Env, Source};
 
-pub struct Var<'a> {
-    keys: Vec<&'a str>,
+/// a check on a raw value, returning why it's invalid
+type Rule<'a> = Box<dyn Fn(&str) -> Result<(), String> + 'a>;
+
+/// a `range` or `validate` check on the parsed value
+type Check<'a, T> = Box<dyn Fn(&T) -> Result<(), String> + 'a>;
+
+/// the `range` and `validate` checks of a `Var` that reads a `T`
+pub struct Checks<'a, T>(Vec<Check<'a, T>>);
+
+impl<T> Default for Checks<'_, T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+/// a lookup of one or more keys; `range` and `validate` fix the type it reads, which `C` then
+/// holds the checks for
+pub struct Var<'a, C = ()> {
+    keys: Vec<Cow<'a, str>>,
+    source: &'a dyn Source,
+    sensitive: bool,
+    schemes: Option<&'a [&'a str]>,
+    normalized: bool,
+    deprecated: Vec<String>,
+    deny_deprecated: bool,
+    /// every `prefix` and `scope` so far, with whether it kept the unprefixed keys
+    prefixes: Vec<(String, bool)>,
+    empty_as: Option<EmptyAs>,
+    location: Location,
+    rules: Vec<Rule<'a>>,
+    checks: C,
 }
 
 impl<'a> Var<'a> {
+    #[track_caller]
     pub fn new(keys: &[&'a str]) -> Self {
-        Self { keys: keys.to_vec() }
+        Self::from_source(&Env, keys)
+    }
+
+    /// remembers where it was called from, for deprecation warnings
+    #[track_caller]
+    pub fn from_source(source: &'a dyn Source, keys: &[&'a str]) -> Self {
+        let caller = std::panic::Location::caller();
+        Self {
+            keys: keys.iter().map(|key| Cow::Borrowed(*key)).collect(),
+            source,
+            sensitive: false,
+            schemes: None,
+            normalized: false,
+            deprecated: Vec::new(),
+            deny_deprecated: false,
+            prefixes: Vec::new(),
+            empty_as: None,
+            location: Location::new(caller.file(), caller.line()),
+            rules: Vec::new(),
+            checks: (),
+        }
+    }
+
+    #[track_caller]
+    pub(crate) fn from_owned_keys(source: &'a dyn Source, keys: Vec<String>) -> Self {
+        Self {
+            keys: keys.into_iter().map(Cow::Owned).collect(),
+            ..Self::from_source(source, &[])
+        }
+    }
+
+    /// reject values outside `range` with `Error::Invalid`, e.g. `range(1..=65535)`; the value is
+    /// read as the range's type
+    pub fn range<N, R>(self, range: R) -> Var<'a, Checks<'a, N>>
+    where
+        N: FromEnvStr + PartialOrd,
+        R: RangeBounds<N> + Debug + 'a,
+    {
+        self.typed::<N>().range(range)
+    }
+
+    /// reject values for which `check` returns an error, e.g. `validate(|size: &usize| ...)`; the
+    /// value is read as `V`
+    pub fn validate<V, E>(self, check: impl Fn(&V) -> Result<(), E> + 'a) -> Var<'a, Checks<'a, V>>
+    where
+        V: FromEnvStr,
+        E: Display,
+    {
+        self.typed::<V>().validate(check)
     }
 
     pub fn get<T: FromEnvStr>(self) -> crate::Result<T> {
-        resolve(&self.keys)
+        self.typed::<T>().get()
+    }
+
+    /// like `get`, but also reports which key and layer produced the value
+    pub fn traced<T: FromEnvStr>(self) -> crate::Result<Resolved<T>> {
+        self.typed::<T>().traced()
+    }
+
+    /// like `get`, for any `FromStr` type; see `Parsed`
+    pub fn parsed<T>(self) -> crate::Result<T>
+    where
+        T: std::str::FromStr,
+        T::Err: Display,
+    {
+        self.get::<Parsed<T>>().map(Parsed::into_inner)
     }
 
     pub fn default<T: FromEnvStr>(self, val: T) -> VarOr<'a, T> {
-        VarOr { keys: self.keys, default: val }
+        self.typed::<T>().default(val)
     }
 
     pub fn default_str(self, s: &'a str) -> VarOrStr<'a> {
-        VarOrStr { keys: self.keys, default: s }
+        VarOrStr { var: self, default: s }
     }
 
     pub fn default_fn<T, F>(self, f: F) -> VarOrElse<'a, T, F>
     where
         F: FnOnce() -> T,
     {
-        VarOrElse {
+        VarOrElse { var: self.typed(), default_fn: f }
+    }
+
+    pub fn resolve_with<T, E, F>(self, parse_fn: F) -> crate::Result<T>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        F: FnOnce(&str) -> std::result::Result<T, E>,
+    {
+        self.typed::<T>().resolve_checked_with(parse_fn)
+    }
+
+    /// the same lookup, read as `N`
+    fn typed<N>(self) -> Var<'a, Checks<'a, N>> {
+        Var {
             keys: self.keys,
-            default_fn: f,
-            _marker: PhantomData,
+            source: self.source,
+            sensitive: self.sensitive,
+            schemes: self.schemes,
+            normalized: self.normalized,
+            deprecated: self.deprecated,
+            deny_deprecated: self.deny_deprecated,
+            prefixes: self.prefixes,
+            empty_as: self.empty_as,
+            location: self.location,
+            rules: self.rules,
+            checks: Checks::default(),
         }
     }
+}
 
-    pub fn resolve_with<T, E, F>(self, parse_fn: F) -> crate::Result<T>
+impl<'a, C> Var<'a, C> {
+    /// put `prefix` in front of every key given so far, and of deprecated keys given later
+    pub fn prefix(self, prefix: &str) -> Self {
+        self.add_prefix(prefix, false)
+    }
+
+    /// try `keys` as they are after every key given so far, e.g. an unprefixed `PORT` after `BILLING_PORT`
+    pub fn or_keys(mut self, keys: &[&'a str]) -> Self {
+        self.keys.extend(keys.iter().map(|key| Cow::Borrowed(*key)));
+        self
+    }
+
+    /// read from the scope's source with its prefix, and its unprefixed fallback if it has one
+    pub fn scope(mut self, scope: &Scope<'a>) -> Self {
+        self.source = scope.source();
+        self.add_prefix(scope.prefix(), scope.has_unprefixed_fallback())
+    }
+
+    fn add_prefix(mut self, prefix: &str, unprefixed_fallback: bool) -> Self {
+        self.keys = expand_keys(prefix, unprefixed_fallback, &self.keys)
+            .into_iter()
+            .map(Cow::Owned)
+            .collect();
+        self.deprecated = expand_keys(prefix, unprefixed_fallback, &self.deprecated);
+        self.prefixes.push((prefix.to_owned(), unprefixed_fallback));
+        self
+    }
+
+    /// mask the raw value in parse errors, whatever type it resolves to
+    pub fn sensitive(mut self) -> Self {
+        self.sensitive = true;
+        self
+    }
+
+    /// only accept values whose URL scheme is one of `schemes`, ignoring case
+    pub fn schemes(mut self, schemes: &'a [&'a str]) -> Self {
+        self.schemes = Some(schemes);
+        self
+    }
+
+    /// old names kept for compatibility; a hit on one logs a warning naming the first other key.
+    /// names get the same `prefix` and `scope` as the other keys, and are added to the end of the
+    /// cascade if they aren't in it yet
+    pub fn deprecated<K: AsRef<str>>(mut self, keys: impl IntoIterator<Item = K>) -> Self {
+        let mut names: Vec<String> = keys.into_iter().map(|key| key.as_ref().to_owned()).collect();
+        for (prefix, unprefixed_fallback) in &self.prefixes {
+            names = expand_keys(prefix, *unprefixed_fallback, &names);
+        }
+        for name in names {
+            if !self.keys.iter().any(|existing| *existing == name) {
+                self.keys.push(Cow::Owned(name.clone()));
+            }
+            self.deprecated.push(name);
+        }
+        self
+    }
+
+    /// make a hit on a deprecated key an `Error::Deprecated` instead of a warning
+    pub fn deny_deprecated(mut self) -> Self {
+        self.deny_deprecated = true;
+        self
+    }
+
+    /// reject values that aren't exactly one of `allowed`
+    pub fn one_of(self, allowed: &'a [&'a str]) -> Self {
+        self.rule(move |raw| if allowed.contains(&raw) { Ok(()) } else { Err(format!("must be one of {}", allowed.join(", "))) })
+    }
+
+    /// reject values that `pattern` doesn't match
+    #[cfg(feature = "regex")]
+    pub fn matches(self, pattern: &'a regex::Regex) -> Self {
+        self.rule(move |raw| if pattern.is_match(raw) { Ok(()) } else { Err(format!("must match {}", pattern.as_str())) })
+    }
+
+    /// reject empty and all-whitespace values
+    pub fn non_empty(self) -> Self {
+        self.rule(|raw| if raw.trim().is_empty() { Err("must not be empty".to_owned()) } else { Ok(()) })
+    }
+
+    fn rule(mut self, rule: impl Fn(&str) -> Result<(), String> + 'a) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// what an empty value means for this lookup, instead of the source's policy
+    pub fn empty_as(mut self, policy: EmptyAs) -> Self {
+        self.empty_as = Some(policy);
+        self
+    }
+
+    /// match keys ignoring case and treating `-`, `.` and `_` alike, so `database-url` answers `DATABASE_URL`
+    pub fn normalize_keys(mut self) -> Self {
+        self.normalized = true;
+        self
+    }
+
+    /// the first key that is set and its raw value
+    fn first_set<'k>(&self, keys: &[&'k str]) -> crate::Result<Option<(&'k str, String)>> {
+        let source = self.view();
+        for key in keys {
+            if let Some(raw) = get_checked(&source, key)? {
+                return Ok(Some((*key, raw.to_string_lossy().into_owned())));
+            }
+        }
+        Ok(None)
+    }
+
+    /// scheme and validation checks on the raw value of the first key that is set, before it's
+    /// parsed; defaults aren't checked
+    fn check_raw(&self, keys: &[&str], expected: &'static str, redact: fn(&str) -> String) -> crate::Result<()> {
+        if self.schemes.is_none() && self.rules.is_empty() {
+            return Ok(());
+        }
+        let Some((key, raw)) = self.first_set(keys)? else {
+            return Ok(());
+        };
+        if let Some(schemes) = self.schemes {
+            check_scheme(&raw, schemes).map_err(|err| Error::Parse {
+                key: self.source.key_name(key),
+                expected,
+                got: mask_url_passwords(&redact(&raw)),
+                source: Box::new(err),
+                origin: Box::new(self.view().origin(key)),
+                location: Location::default(),
+            })?;
+        }
+        for rule in &self.rules {
+            rule(&raw).map_err(|reason| Error::Invalid {
+                key: self.source.key_name(key),
+                got: redact(&raw),
+                reason,
+                location: Location::default(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// the source under this lookup's `EmptyAs` policy
+    fn view(&self) -> WithEmptyAs<'a> {
+        WithEmptyAs {
+            source: self.source,
+            policy: self.empty_as.unwrap_or_else(|| self.source.empty_as()),
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.keys.iter().map(AsRef::as_ref).collect()
+    }
+
+    fn is_deprecated(&self, key: &str) -> bool {
+        self.deprecated.iter().any(|name| name == key)
+    }
+
+    /// warns, or fails under `deny_deprecated`, when the first key that is set is deprecated
+    fn check_deprecated(&self, keys: &[&str]) -> crate::Result<()> {
+        if self.deprecated.is_empty() {
+            return Ok(());
+        }
+        let source = self.view();
+        let Some(index) = keys.iter().position(|key| get_raw(&source, key).is_some()) else {
+            return Ok(());
+        };
+        if !self.is_deprecated(&self.keys[index]) {
+            return Ok(());
+        }
+        let key = self.source.key_name(keys[index]);
+        let replacement = self
+            .keys
+            .iter()
+            .find(|name| !self.is_deprecated(name))
+            .map(|name| self.source.key_name(name));
+        if self.deny_deprecated {
+            return Err(Error::Deprecated {
+                key,
+                replacement,
+                location: self.location.clone(),
+            });
+        }
+        tracing::warn!(
+            key = key.as_str(),
+            replacement = replacement.as_deref(),
+            file = self.location.file,
+            line = self.location.line,
+            "deprecated environment variable is set"
+        );
+        Ok(())
+    }
+
+    /// runs `resolve` with the keys as the source spells them
+    fn lookup<U>(&self, resolve: impl FnOnce(&[&str]) -> crate::Result<U>) -> crate::Result<U> {
+        let run = |keys: &[&str]| self.check_deprecated(keys).and_then(|()| resolve(keys));
+        let result = if self.normalized {
+            match_keys(&self.view(), &self.keys()).and_then(|keys| run(&keys.iter().map(String::as_str).collect::<Vec<_>>()))
+        } else {
+            run(&self.keys())
+        };
+        self.finish(result)
+    }
+
+    fn finish<U>(&self, result: crate::Result<U>) -> crate::Result<U> {
+        if self.sensitive { result.map_err(Error::redacted) } else { result }
+    }
+}
+
+impl<'a, T> Var<'a, Checks<'a, T>> {
+    fn resolve_checked_with<E, F>(self, parse_fn: F) -> crate::Result<T>
     where
         E: std::error::Error + Send + Sync + 'static,
         F: FnOnce(&str) -> std::result::Result<T, E>,
     {
-        resolve_with(&self.keys, parse_fn)
+        self.lookup(|keys| {
+            self.check_raw(keys, std::any::type_name::<T>(), str::to_owned)?;
+            let value = resolve_with_from(&self.view(), keys, parse_fn)?;
+            self.check_value(keys, &value, str::to_owned)?;
+            Ok(value)
+        })
+    }
+
+    /// `range` and `validate` checks on a value parsed from the first key that is set; defaults
+    /// aren't checked
+    fn check_value(&self, keys: &[&str], value: &T, redact: fn(&str) -> String) -> crate::Result<()> {
+        if self.checks.0.is_empty() {
+            return Ok(());
+        }
+        let Some((key, raw)) = self.first_set(keys)? else {
+            return Ok(());
+        };
+        for check in &self.checks.0 {
+            check(value).map_err(|reason| Error::Invalid {
+                key: self.source.key_name(key),
+                got: redact(&raw),
+                reason,
+                location: Location::default(),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: FromEnvStr> Var<'a, Checks<'a, T>> {
+    /// reject values outside `range` with `Error::Invalid`
+    pub fn range<R>(self, range: R) -> Self
+    where
+        T: PartialOrd,
+        R: RangeBounds<T> + Debug + 'a,
+    {
+        self.validate(move |value: &T| if range.contains(value) { Ok(()) } else { Err(format!("must be in {range:?}")) })
+    }
+
+    /// reject values for which `check` returns an error
+    pub fn validate<E: Display>(mut self, check: impl Fn(&T) -> Result<(), E> + 'a) -> Self {
+        self.checks
+            .0
+            .push(Box::new(move |value| check(value).map_err(|err| err.to_string())));
+        self
+    }
+
+    pub fn get(self) -> crate::Result<T> {
+        self.lookup(|keys| self.checked(keys, || resolve_from(&self.view(), keys), |value| value))
+    }
+
+    /// like `get`, but also reports which key and layer produced the value
+    pub fn traced(self) -> crate::Result<Resolved<T>> {
+        self.lookup(|keys| self.checked(keys, || resolve_traced_from(&self.view(), keys), |resolved| &resolved.value))
+    }
+
+    /// like `get`, parsing with `FromStr`; see `Parsed`
+    pub fn parsed(self) -> crate::Result<T>
+    where
+        T: std::str::FromStr,
+        <T as std::str::FromStr>::Err: Display,
+    {
+        self.lookup(|keys| self.checked(keys, || resolve_from(&self.view(), keys).map(Parsed::into_inner), |value| value))
+    }
+
+    pub fn default(self, val: T) -> VarOr<'a, T> {
+        VarOr { var: self, default: val }
+    }
+
+    pub fn default_str(self, s: &'a str) -> VarOrStr<'a, Checks<'a, T>> {
+        VarOrStr { var: self, default: s }
+    }
+
+    pub fn default_fn<F: FnOnce() -> T>(self, f: F) -> VarOrElse<'a, T, F> {
+        VarOrElse { var: self, default_fn: f }
+    }
+
+    pub fn resolve_with<E, F>(self, parse_fn: F) -> crate::Result<T>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        F: FnOnce(&str) -> std::result::Result<T, E>,
+    {
+        self.resolve_checked_with(parse_fn)
+    }
+
+    /// the raw checks, `resolve`, then the checks on the value it produced; check errors are
+    /// masked for sensitive types
+    fn checked<U>(&self, keys: &[&str], resolve: impl FnOnce() -> crate::Result<U>, value: fn(&U) -> &T) -> crate::Result<U> {
+        let conceal = |err: Error| if T::is_sensitive() { err.redacted() } else { err };
+        self.check_raw(keys, T::type_name(), T::redact_raw).map_err(conceal)?;
+        let resolved = resolve()?;
+        self.check_value(keys, value(&resolved), T::redact_raw).map_err(conceal)?;
+        Ok(resolved)
     }
 }
 
 pub struct VarOr<'a, T> {
-    keys: Vec<&'a str>,
+    var: Var<'a, Checks<'a, T>>,
     default: T,
 }
 
-impl<'a, T: FromEnvStr> VarOr<'a, T> {
+impl<T: FromEnvStr> VarOr<'_, T> {
     pub fn get(self) -> crate::Result<T> {
-        resolve_or(&self.keys, self.default)
+        self.var.lookup(|keys| {
+            self.var
+                .checked(keys, || resolve_or_from(&self.var.view(), keys, self.default), |value| value)
+        })
     }
 }
 
-pub struct VarOrStr<'a> {
-    keys: Vec<&'a str>,
+pub struct VarOrStr<'a, C = ()> {
+    var: Var<'a, C>,
     default: &'a str,
 }
 
-impl<'a> VarOrStr<'a> {
+impl VarOrStr<'_> {
     pub fn get<T: FromEnvStr>(self) -> crate::Result<T> {
-        resolve_or_parse(&self.keys, self.default)
+        VarOrStr {
+            var: self.var.typed::<T>(),
+            default: self.default,
+        }
+        .get()
+    }
+}
+
+impl<'a, T: FromEnvStr> VarOrStr<'a, Checks<'a, T>> {
+    pub fn get(self) -> crate::Result<T> {
+        self.var.lookup(|keys| {
+            self.var
+                .checked(keys, || resolve_or_parse_from(&self.var.view(), keys, self.default), |value| value)
+        })
     }
 }
 
 pub struct VarOrElse<'a, T, F> {
-    keys: Vec<&'a str>,
+    var: Var<'a, Checks<'a, T>>,
     default_fn: F,
-    _marker: PhantomData<T>,
 }
 
-impl<'a, T: FromEnvStr, F: FnOnce() -> T> VarOrElse<'a, T, F> {
+impl<T: FromEnvStr, F: FnOnce() -> T> VarOrElse<'_, T, F> {
     pub fn get(self) -> crate::Result<T> {
-        resolve_or_else(&self.keys, self.default_fn)
+        self.var.lookup(|keys| {
+            self.var
+                .checked(keys, || resolve_or_else_from(&self.var.view(), keys, self.default_fn), |value| value)
+        })
     }
 }
 
@@ -243,4 +681,435 @@ mod tests {
             assert!(result.is_err());
         });
     }
+
+    #[test]
+    fn from_source_get() {
+        let map = std::collections::HashMap::from([("BUILDER_SRC", "8080")]);
+        temp_env::with_vars([("BUILDER_SRC", Some("1"))], || {
+            let result = Var::from_source(&map, &["BUILDER_SRC"]).get::<u16>();
+            assert_eq!(result.ok(), Some(8080));
+        });
+    }
+
+    #[test]
+    fn from_source_default_when_missing() {
+        let map = std::collections::HashMap::<String, String>::new();
+        let result = Var::from_source(&map, &["BUILDER_SRC_MISS"]).default(9090u16).get();
+        assert_eq!(result.ok(), Some(9090));
+    }
+
+    #[test]
+    fn from_source_resolve_with() {
+        let map = std::collections::HashMap::from([("BUILDER_SRC_RW", "a:b")]);
+        let result = Var::from_source(&map, &["BUILDER_SRC_RW"]).resolve_with(|raw| -> std::result::Result<Vec<String>, std::convert::Infallible> { Ok(raw.split(':').map(str::to_owned).collect()) });
+        assert_eq!(result.ok(), Some(vec!["a".to_owned(), "b".to_owned()]));
+    }
+
+    #[test]
+    fn traced_reports_origin() {
+        let local = std::collections::HashMap::from([("BUILDER_TRACED", "8080")]);
+        let layered = crate::source::Layered::new().layer(".env.local", &local);
+        let resolved = Var::from_source(&layered, &["BUILDER_TRACED"])
+            .traced::<u16>()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(resolved.value, 8080);
+        assert_eq!(resolved.key.as_deref(), Some("BUILDER_TRACED"));
+        assert_eq!(resolved.origin.layer.as_deref(), Some(".env.local"));
+    }
+
+    #[test]
+    fn sensitive_masks_parse_error() {
+        temp_env::with_vars([("BUILDER_SENSITIVE", Some("hunter2"))], || {
+            let err = Var::new(&["BUILDER_SENSITIVE"])
+                .sensitive()
+                .get::<bool>()
+                .err()
+                .unwrap_or_else(|| panic!("expected an error"));
+            assert!(!err.to_string().contains("hunter2"), "{err}");
+        });
+    }
+
+    #[test]
+    fn sensitive_masks_default_str_error() {
+        temp_env::with_vars([("BUILDER_SENSITIVE_DSTR", None::<&str>)], || {
+            let err = Var::new(&["BUILDER_SENSITIVE_DSTR"])
+                .sensitive()
+                .default_str("hunter2")
+                .get::<u16>()
+                .err()
+                .unwrap_or_else(|| panic!("expected an error"));
+            assert!(!err.to_string().contains("hunter2"), "{err}");
+        });
+    }
+
+    #[test]
+    fn sensitive_masks_resolve_with_error() {
+        temp_env::with_vars([("BUILDER_SENSITIVE_RW", Some("hunter2"))], || {
+            let err = Var::new(&["BUILDER_SENSITIVE_RW"])
+                .sensitive()
+                .resolve_with(|raw| raw.parse::<i32>())
+                .err()
+                .unwrap_or_else(|| panic!("expected an error"));
+            assert!(!err.to_string().contains("hunter2"), "{err}");
+        });
+    }
+
+    #[test]
+    fn schemes_checks_the_key_that_is_used() {
+        temp_env::with_vars([("BUILDER_SCHEMES_A", None::<&str>), ("BUILDER_SCHEMES_B", Some("https://example.com"))], || {
+            let ok = Var::new(&["BUILDER_SCHEMES_A", "BUILDER_SCHEMES_B"])
+                .schemes(&["https"])
+                .get::<String>();
+            assert_eq!(ok.ok().as_deref(), Some("https://example.com"));
+            let err = Var::new(&["BUILDER_SCHEMES_B"])
+                .schemes(&["postgres"])
+                .get::<String>()
+                .err()
+                .unwrap_or_else(|| panic!("expected an error"));
+            assert!(matches!(err, Error::Parse { ref key, .. } if key == "BUILDER_SCHEMES_B"), "{err}");
+        });
+    }
+
+    #[test]
+    fn schemes_error_masks_password_for_string() {
+        let source = std::collections::HashMap::from([("DATABASE_URL", "mysql://app:hunter2@db")]);
+        let err = Var::from_source(&source, &["DATABASE_URL"])
+            .schemes(&["postgres"])
+            .get::<String>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Parse { ref got, .. } if got == "mysql://app:****@db"), "{err}");
+        assert!(!err.to_string().contains("hunter2"), "{err}");
+    }
+
+    #[test]
+    fn schemes_skipped_for_defaults() {
+        temp_env::with_vars([("BUILDER_SCHEMES_DEF", None::<&str>)], || {
+            let result = Var::new(&["BUILDER_SCHEMES_DEF"])
+                .schemes(&["https"])
+                .default_str("http://localhost")
+                .get::<String>();
+            assert_eq!(result.ok().as_deref(), Some("http://localhost"));
+        });
+    }
+
+    #[test]
+    fn normalize_keys_matches_other_spellings() {
+        let map = std::collections::HashMap::from([("database-url", "postgres://db"), ("Port", "http")]);
+        let url: String = Var::from_source(&map, &["DATABASE_URL"])
+            .normalize_keys()
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(url, "postgres://db");
+
+        let err = Var::from_source(&map, &["PORT"])
+            .normalize_keys()
+            .default(80u16)
+            .get()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Parse { ref key, .. } if key == "Port"), "{err}");
+
+        let err = Var::from_source(&map, &["DATABASE_URL"])
+            .get::<String>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { .. }), "{err}");
+    }
+
+    #[test]
+    fn normalize_keys_reports_ambiguity() {
+        let map = std::collections::HashMap::from([("database_url", "a"), ("Database.Url", "b")]);
+        let err = Var::from_source(&map, &["DATABASE_URL"])
+            .normalize_keys()
+            .default_str("c")
+            .get::<String>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::AmbiguousKey { ref key, .. } if key == "DATABASE_URL"), "{err}");
+    }
+
+    #[test]
+    fn deprecated_key_still_resolves() {
+        let map = std::collections::HashMap::from([("APP_DB_URL", "postgres://old")]);
+        let url: String = Var::from_source(&map, &["DATABASE_URL", "APP_DB_URL"])
+            .deprecated(["APP_DB_URL"])
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(url, "postgres://old");
+        let url: String = Var::from_source(&map, &["DATABASE_URL"])
+            .deprecated(["APP_DB_URL"])
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(url, "postgres://old");
+    }
+
+    #[test]
+    fn deny_deprecated_reports_replacement_and_caller() {
+        let map = std::collections::HashMap::from([("APP_DB_URL", "postgres://old")]);
+        let line = line!() + 1;
+        let err = Var::from_source(&map, &["DATABASE_URL"])
+            .deprecated(["APP_DB_URL"])
+            .deny_deprecated()
+            .default_str("x")
+            .get::<String>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(
+            matches!(err, Error::Deprecated { ref key, ref replacement, ref location } if key == "APP_DB_URL" && replacement.as_deref() == Some("DATABASE_URL") && location.file == file!() && location.line == line),
+            "{err:?}"
+        );
+        assert!(err.to_string().ends_with("APP_DB_URL is deprecated, use DATABASE_URL instead"), "{err}");
+    }
+
+    #[test]
+    fn deny_deprecated_ignores_current_keys() {
+        let map = std::collections::HashMap::from([("DATABASE_URL", "postgres://new"), ("APP_DB_URL", "postgres://old")]);
+        let url: String = Var::from_source(&map, &["DATABASE_URL"])
+            .deprecated(["APP_DB_URL"])
+            .deny_deprecated()
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(url, "postgres://new");
+    }
+
+    #[test]
+    fn deprecated_keys_follow_prefix_and_scope() {
+        let map = std::collections::HashMap::from([("BILLING_OLD_PORT", "1")]);
+        let scope = Scope::from_source("BILLING_", &map);
+        let vars = [
+            Var::from_source(&map, &["PORT"]).prefix("BILLING_").deprecated(["OLD_PORT"]),
+            Var::from_source(&map, &["PORT"]).deprecated(["OLD_PORT"]).prefix("BILLING_"),
+            Var::from_source(&map, &["PORT"]).scope(&scope).deprecated(["OLD_PORT"]),
+            scope.var(&["PORT"]).deprecated(["OLD_PORT"]),
+        ];
+        for var in vars {
+            assert_eq!(var.keys(), vec!["BILLING_PORT", "BILLING_OLD_PORT"]);
+            let err = var
+                .deny_deprecated()
+                .get::<u16>()
+                .err()
+                .unwrap_or_else(|| panic!("expected an error"));
+            assert!(
+                matches!(err, Error::Deprecated { ref key, ref replacement, .. } if key == "BILLING_OLD_PORT" && replacement.as_deref() == Some("BILLING_PORT")),
+                "{err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn deprecated_keys_follow_unprefixed_fallback() {
+        let map = std::collections::HashMap::from([("OLD_PORT", "1")]);
+        let scope = Scope::from_source("BILLING_", &map).unprefixed_fallback();
+        let var = scope.var(&["PORT"]).deprecated(["OLD_PORT"]);
+        assert_eq!(var.keys(), vec!["BILLING_PORT", "PORT", "BILLING_OLD_PORT", "OLD_PORT"]);
+        let err = var
+            .deny_deprecated()
+            .get::<u16>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Deprecated { ref key, .. } if key == "OLD_PORT"), "{err:?}");
+    }
+
+    #[test]
+    fn deny_deprecated_matches_normalized_spelling() {
+        let map = std::collections::HashMap::from([("app-db-url", "postgres://old")]);
+        let err = Var::from_source(&map, &["DATABASE_URL"])
+            .deprecated(["APP_DB_URL"])
+            .normalize_keys()
+            .deny_deprecated()
+            .get::<String>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Deprecated { ref key, .. } if key == "app-db-url"), "{err:?}");
+    }
+
+    #[rstest::rstest]
+    #[case("8080", true)]
+    #[case("0", false)]
+    #[case("70000", false)]
+    fn range_checks_parsed_number(#[case] raw: &str, #[case] valid: bool) {
+        let map = std::collections::HashMap::from([("PORT", raw)]);
+        let result: crate::Result<u32> = Var::from_source(&map, &["PORT"]).range(1..=65535).get();
+        match result {
+            Ok(port) => assert!(valid, "{port}"),
+            Err(err) => {
+                assert!(!valid, "{err}");
+                assert!(
+                    matches!(err, Error::Invalid { ref key, ref got, ref reason, .. } if key == "PORT" && got == raw && reason == "must be in 1..=65535"),
+                    "{err}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn range_leaves_non_numbers_to_parse_error() {
+        let map = std::collections::HashMap::from([("PORT", "http")]);
+        let port: crate::Result<u16> = Var::from_source(&map, &["PORT"]).range(1..=65535).get();
+        let err = port.err().unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Parse { .. }), "{err}");
+    }
+
+    #[rstest::rstest]
+    #[case("150.5", false)]
+    #[case("50.5", true)]
+    fn range_checks_floats(#[case] raw: &str, #[case] valid: bool) {
+        let map = std::collections::HashMap::from([("RATIO", raw)]);
+        let ratio: crate::Result<f64> = Var::from_source(&map, &["RATIO"]).range(0.0..=100.0).default(1.0).get();
+        assert_eq!(ratio.is_ok(), valid, "{ratio:?}");
+    }
+
+    #[test]
+    fn range_is_read_as_the_value_type() {
+        // the literals would be i32 on their own, which "5000000000" doesn't fit
+        let map = std::collections::HashMap::from([("MAX_BYTES", "5000000000")]);
+        let max: crate::Result<u64> = Var::from_source(&map, &["MAX_BYTES"]).range(1..=1_000_000).get();
+        let err = max.err().unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Invalid { ref reason, .. } if reason == "must be in 1..=1000000"), "{err}");
+    }
+
+    #[test]
+    fn one_of_and_non_empty() {
+        let map = std::collections::HashMap::from([("LEVEL", "trace"), ("NAME", "  ")]);
+        let err = Var::from_source(&map, &["LEVEL"])
+            .one_of(&["debug", "info"])
+            .get::<String>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert_eq!(err.to_string(), "LEVEL: 'trace' is invalid: must be one of debug, info");
+        let err = Var::from_source(&map, &["NAME"])
+            .non_empty()
+            .default_str("app")
+            .get::<String>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Invalid { ref reason, .. } if reason == "must not be empty"), "{err}");
+    }
+
+    #[test]
+    fn validate_runs_custom_check() {
+        let map = std::collections::HashMap::from([("POOL_SIZE", "3")]);
+        let even = |size: &usize| if size.is_multiple_of(2) { Ok(()) } else { Err("must be even") };
+        let err = Var::from_source(&map, &["POOL_SIZE"])
+            .validate(even)
+            .get()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Invalid { ref reason, .. } if reason == "must be even"), "{err}");
+        let size: usize = Var::from_source(&map, &["POOL_SIZE"])
+            .validate(|size: &usize| if *size > 0 { Ok(()) } else { Err("zero") })
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(size, 3);
+    }
+
+    #[test]
+    fn validation_skips_defaults_and_masks_sensitive_values() {
+        let empty = std::collections::HashMap::<&str, &str>::new();
+        let port: u16 = Var::from_source(&empty, &["PORT"])
+            .range(1..=10)
+            .default(80u16)
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(port, 80);
+
+        let map = std::collections::HashMap::from([("API_TOKEN", "hunter2")]);
+        let err = Var::from_source(&map, &["API_TOKEN"])
+            .one_of(&["a"])
+            .sensitive()
+            .get::<String>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(!err.to_string().contains("hunter2"), "{err}");
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn matches_checks_pattern() {
+        let pattern = regex::Regex::new("^[a-z]+$").unwrap_or_else(|err| panic!("{err}"));
+        let map = std::collections::HashMap::from([("REGION", "EU-1")]);
+        let err = Var::from_source(&map, &["REGION"])
+            .matches(&pattern)
+            .get::<String>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Invalid { ref reason, .. } if reason == "must match ^[a-z]+$"), "{err}");
+    }
+
+    #[test]
+    fn empty_as_unset_falls_through() {
+        let map = std::collections::HashMap::from([("APP_PORT", ""), ("PORT", "3000"), ("TIMEOUT", "")]);
+        let port: u16 = Var::from_source(&map, &["APP_PORT", "PORT"])
+            .empty_as(EmptyAs::Unset)
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(port, 3000);
+        let timeout: u16 = Var::from_source(&map, &["TIMEOUT"])
+            .empty_as(EmptyAs::Unset)
+            .default(30u16)
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(timeout, 30);
+        let timeout: Option<u16> = Var::from_source(&map, &["TIMEOUT"])
+            .empty_as(EmptyAs::Unset)
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(timeout, None);
+        let err = Var::from_source(&map, &["TIMEOUT"])
+            .empty_as(EmptyAs::Unset)
+            .get::<u16>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { .. }), "{err}");
+    }
+
+    #[test]
+    fn empty_as_value_keeps_empty_string() {
+        let map = std::collections::HashMap::from([("APP_PORT", ""), ("PORT", "3000")]);
+        let err = Var::from_source(&map, &["APP_PORT", "PORT"])
+            .empty_as(EmptyAs::Value)
+            .get::<u16>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Parse { ref key, .. } if key == "APP_PORT"), "{err}");
+        let name: String = Var::from_source(&map, &["APP_PORT"])
+            .empty_as(EmptyAs::Value)
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(name, "");
+    }
+
+    #[test]
+    fn empty_as_error_rejects_empty_string() {
+        let map = std::collections::HashMap::from([("APP_PORT", ""), ("PORT", "3000")]);
+        let err = Var::from_source(&map, &["APP_PORT", "PORT"])
+            .empty_as(EmptyAs::Error)
+            .default(80u16)
+            .get()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Invalid { ref key, ref reason, .. } if key == "APP_PORT" && reason == "must not be empty"), "{err}");
+        let err = Var::from_source(&map, &["APP_PORT"])
+            .empty_as(EmptyAs::Error)
+            .one_of(&["a"])
+            .get::<String>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Invalid { ref reason, .. } if reason == "must not be empty"), "{err}");
+    }
+
+    #[test]
+    fn parsed_reads_from_str_types() {
+        let map = std::collections::HashMap::from([("WORKERS", "4"), ("BAD", "0")]);
+        let workers: std::num::NonZeroUsize = Var::from_source(&map, &["WORKERS"])
+            .parsed()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(workers.get(), 4);
+        let err = Var::from_source(&map, &["BAD"])
+            .parsed::<std::num::NonZeroUsize>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Parse { expected, .. } if expected == std::any::type_name::<std::num::NonZeroUsize>()), "{err}");
+    }
 }
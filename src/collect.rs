@@ -0,0 +1,89 @@
+use crate::error::{Error, Errors};
+
+/// gathers failures from many lookups so they can be reported at once
+#[derive(Debug, Default)]
+pub struct Collector {
+    errors: Vec<Error>,
+}
+
+impl Collector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// record the error, if any, and hand back the value
+    pub fn take<T>(&mut self, result: crate::error::Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.errors.push(err);
+                None
+            }
+        }
+    }
+
+    pub fn push(&mut self, err: Error) {
+        self.errors.push(err);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// `Ok` when nothing failed, otherwise every recorded error in lookup order
+    pub fn finish(self) -> std::result::Result<(), Errors> {
+        if self.errors.is_empty() { Ok(()) } else { Err(Errors::new(self.errors)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finish_ok_when_nothing_failed() {
+        temp_env::with_vars([("TEST_COLLECT_OK", Some("8080"))], || {
+            let mut collector = Collector::new();
+            let port: Option<u16> = collector.take(crate::env!("TEST_COLLECT_OK"));
+            assert_eq!(port, Some(8080));
+            assert!(collector.finish().is_ok());
+        });
+    }
+
+    #[test]
+    fn finish_reports_every_failure() {
+        temp_env::with_vars(
+            [("TEST_COLLECT_MISSING", None::<&str>), ("TEST_COLLECT_BAD", Some("banana")), ("TEST_COLLECT_GOOD", Some("yes"))],
+            || {
+                let mut collector = Collector::new();
+                let missing: Option<String> = collector.take(crate::env!("TEST_COLLECT_MISSING"));
+                let bad: Option<u16> = collector.take(crate::Var::new(&["TEST_COLLECT_BAD"]).get());
+                let good: Option<bool> = collector.take(crate::env!("TEST_COLLECT_GOOD"));
+                assert_eq!((missing, bad, good), (None, None, Some(true)));
+
+                let errors = collector.finish().err().unwrap_or_else(|| panic!("expected an error"));
+                assert_eq!(errors.len(), 2);
+                let mut iter = errors.iter();
+                assert!(matches!(iter.next(), Some(Error::NotFound { .. })));
+                assert!(matches!(iter.next(), Some(Error::Parse { .. })));
+
+                let msg = errors.to_string();
+                assert!(msg.contains("collect.rs:"), "{msg}");
+                assert!(msg.contains("TEST_COLLECT_MISSING"), "{msg}");
+                assert!(msg.contains("TEST_COLLECT_BAD"), "{msg}");
+            },
+        );
+    }
+
+    #[test]
+    fn push_records_error() {
+        let mut collector = Collector::new();
+        collector.push(Error::NotFound {
+            keys: "PORT".into(),
+            suggestions: Vec::new(),
+            location: crate::error::Location::default(),
+        });
+        assert!(!collector.is_empty());
+        assert!(collector.finish().is_err());
+    }
+}
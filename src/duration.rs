@@ -0,0 +1,275 @@
+use std::time::Duration;
+
+use crate::parse::FromEnvStr;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// unit suffixes and their length in nanoseconds; a bare number is in seconds
+const UNITS: &[(&[&str], u128)] = &[
+    (&["ns", "nsec", "nanos"], 1),
+    (&["us", "µs", "usec", "micros"], 1_000),
+    (&["ms", "msec", "millis"], 1_000_000),
+    (&["s", "sec", "secs", "second", "seconds"], NANOS_PER_SEC),
+    (&["m", "min", "mins", "minute", "minutes"], 60 * NANOS_PER_SEC),
+    (&["h", "hr", "hrs", "hour", "hours"], 3_600 * NANOS_PER_SEC),
+    (&["d", "day", "days"], 86_400 * NANOS_PER_SEC),
+    (&["w", "week", "weeks"], 604_800 * NANOS_PER_SEC),
+];
+
+/// fractional digits beyond this can't change the result
+const MAX_FRACTION_DIGITS: usize = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Problem {
+    Empty,
+    Unexpected,
+    MissingNumber,
+    InvalidNumber,
+    MissingUnit,
+    UnknownUnit,
+    Overflow,
+}
+
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct DurationParseError {
+    value: String,
+    token: String,
+    problem: Problem,
+}
+
+impl std::fmt::Display for DurationParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let token = &self.token;
+        match self.problem {
+            Problem::Empty => write!(formatter, "cannot parse an empty string as duration"),
+            Problem::Unexpected => write!(formatter, "cannot parse '{}' as duration: unexpected '{token}'", self.value),
+            Problem::MissingNumber => write!(formatter, "cannot parse '{}' as duration: expected a number before '{token}'", self.value),
+            Problem::InvalidNumber => write!(formatter, "cannot parse '{}' as duration: invalid number in '{token}'", self.value),
+            Problem::MissingUnit => write!(formatter, "cannot parse '{}' as duration: missing unit after '{token}'", self.value),
+            Problem::UnknownUnit => write!(formatter, "cannot parse '{}' as duration: unknown unit in '{token}'", self.value),
+            Problem::Overflow => write!(formatter, "cannot parse '{}' as duration: '{token}' is out of range", self.value),
+        }
+    }
+}
+
+impl std::error::Error for DurationParseError {}
+
+/// total nanoseconds of `text`, e.g. `30`, `250ms`, `1h30m` or `1.5h`
+fn parse_nanos(value: &str, text: &str) -> Result<u128, DurationParseError> {
+    let error = |token: &str, problem: Problem| DurationParseError {
+        value: value.to_owned(),
+        token: token.to_owned(),
+        problem,
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(error("", Problem::Empty));
+    }
+
+    let mut total: u128 = 0;
+    let mut rest = text;
+    let mut tokens = 0usize;
+    while !rest.is_empty() {
+        let number_len = rest.find(|ch: char| !(ch.is_ascii_digit() || ch == '.')).unwrap_or(rest.len());
+        // allow `2 minutes` as well as `2minutes`
+        let spaced = &rest[number_len..];
+        let gap = match spaced.trim_start() {
+            unit if number_len > 0 && unit.starts_with(char::is_alphabetic) => spaced.len() - unit.len(),
+            _ => 0,
+        };
+        let unit_start = number_len + gap;
+        let unit_len = rest[unit_start..]
+            .find(|ch: char| !ch.is_alphabetic())
+            .unwrap_or(rest.len() - unit_start);
+        if unit_start + unit_len == 0 {
+            let bad = rest.split_whitespace().next().unwrap_or(rest);
+            return Err(error(bad, Problem::Unexpected));
+        }
+        let (token, after) = rest.split_at(unit_start + unit_len);
+        let (number, unit) = (&token[..number_len], &token[unit_start..]);
+        if number.is_empty() {
+            return Err(error(unit, Problem::MissingNumber));
+        }
+        let unit_nanos = if unit.is_empty() {
+            // only a lone number may leave out its unit
+            if tokens > 0 || !after.trim().is_empty() {
+                return Err(error(token, Problem::MissingUnit));
+            }
+            NANOS_PER_SEC
+        } else {
+            let lower = unit.to_lowercase();
+            UNITS
+                .iter()
+                .find(|(names, _)| names.contains(&lower.as_str()))
+                .map(|(_, nanos)| *nanos)
+                .ok_or_else(|| error(token, Problem::UnknownUnit))?
+        };
+        let nanos = scale(number, unit_nanos).map_err(|problem| error(token, problem))?;
+        total = total.checked_add(nanos).ok_or_else(|| error(token, Problem::Overflow))?;
+        tokens += 1;
+        rest = after.trim_start();
+    }
+    Ok(total)
+}
+
+/// `number` (digits with an optional fraction) times `unit_nanos`
+fn scale(number: &str, unit_nanos: u128) -> Result<u128, Problem> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if (whole.is_empty() && fraction.is_empty()) || fraction.contains('.') {
+        return Err(Problem::InvalidNumber);
+    }
+    let whole: u128 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| Problem::Overflow)? };
+    let mut nanos = whole.checked_mul(unit_nanos).ok_or(Problem::Overflow)?;
+    let fraction = &fraction[..fraction.len().min(MAX_FRACTION_DIGITS)];
+    if !fraction.is_empty() {
+        let digits: u128 = fraction.parse().map_err(|_| Problem::InvalidNumber)?;
+        let fraction_nanos = digits * unit_nanos / 10u128.pow(fraction.len() as u32);
+        nanos = nanos.checked_add(fraction_nanos).ok_or(Problem::Overflow)?;
+    }
+    Ok(nanos)
+}
+
+fn to_duration(value: &str, nanos: u128) -> Result<Duration, DurationParseError> {
+    let secs = u64::try_from(nanos / NANOS_PER_SEC).map_err(|_| DurationParseError {
+        value: value.to_owned(),
+        token: value.trim().to_owned(),
+        problem: Problem::Overflow,
+    })?;
+    Ok(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+}
+
+/// accepts compound units like `1h30m`, `250ms` or `1.5s`; a bare number is in seconds
+impl FromEnvStr for Duration {
+    type Err = DurationParseError;
+
+    fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        to_duration(value, parse_nanos(value, value)?)
+    }
+
+    fn type_name() -> &'static str {
+        "Duration"
+    }
+}
+
+/// same syntax as `std::time::Duration`, with an optional leading `-`
+#[cfg(feature = "chrono")]
+impl FromEnvStr for chrono::TimeDelta {
+    type Err = DurationParseError;
+
+    fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let trimmed = value.trim();
+        let (negative, magnitude) = match trimmed.strip_prefix('-') {
+            Some(magnitude) => (true, magnitude),
+            None => (false, trimmed),
+        };
+        let duration = to_duration(value, parse_nanos(value, magnitude)?)?;
+        let delta = chrono::TimeDelta::from_std(duration).map_err(|_| DurationParseError {
+            value: value.to_owned(),
+            token: trimmed.to_owned(),
+            problem: Problem::Overflow,
+        })?;
+        Ok(if negative { -delta } else { delta })
+    }
+
+    fn type_name() -> &'static str {
+        "TimeDelta"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("30", Duration::from_secs(30))]
+    #[case("30s", Duration::from_secs(30))]
+    #[case("5m", Duration::from_secs(300))]
+    #[case("1h30m", Duration::from_secs(5_400))]
+    #[case("1h 30m 15s", Duration::from_secs(5_415))]
+    #[case("250ms", Duration::from_millis(250))]
+    #[case("10us", Duration::from_micros(10))]
+    #[case("10µs", Duration::from_micros(10))]
+    #[case("7ns", Duration::from_nanos(7))]
+    #[case("2d", Duration::from_secs(172_800))]
+    #[case("1w", Duration::from_secs(604_800))]
+    #[case("1.5h", Duration::from_secs(5_400))]
+    #[case(".5s", Duration::from_millis(500))]
+    #[case("1.5", Duration::from_millis(1_500))]
+    #[case("2 minutes", Duration::from_secs(120))]
+    #[case("1H", Duration::from_secs(3_600))]
+    #[case("  45s  ", Duration::from_secs(45))]
+    fn parse_duration_valid(#[case] input: &str, #[case] expected: Duration) {
+        assert_eq!(Duration::from_env_str(input).unwrap_or_else(|err| panic!("{err}")), expected);
+    }
+
+    #[rstest]
+    #[case("", "cannot parse an empty string as duration")]
+    #[case("5x", "unknown unit in '5x'")]
+    #[case("1h30", "missing unit after '30'")]
+    #[case("1h 30 15s", "missing unit after '30'")]
+    #[case("h", "expected a number before 'h'")]
+    #[case("1.2.3s", "invalid number in '1.2.3s'")]
+    #[case("1h+30m", "unexpected '+30m'")]
+    #[case("-5s", "unexpected '-5s'")]
+    #[case("99999999999999999999999h", "'99999999999999999999999h' is out of range")]
+    fn parse_duration_error_names_token(#[case] input: &str, #[case] message: &str) {
+        let err = Duration::from_env_str(input)
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"))
+            .to_string();
+        assert!(err.contains(message), "{err}");
+    }
+
+    #[test]
+    fn parse_duration_overflowing_sum() {
+        let err = Duration::from_env_str("20000000000000w 20000000000000w")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"))
+            .to_string();
+        assert!(err.contains("out of range"), "{err}");
+    }
+
+    #[test]
+    fn duration_type_name() {
+        assert_eq!(Duration::type_name(), "Duration");
+    }
+
+    #[test]
+    fn duration_in_vec() {
+        let parsed: Vec<Duration> = Vec::from_env_str("1s, 250ms").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(parsed, vec![Duration::from_secs(1), Duration::from_millis(250)]);
+    }
+
+    #[cfg(feature = "chrono")]
+    mod chrono_tests {
+        use rstest::rstest;
+
+        use crate::parse::FromEnvStr;
+
+        #[rstest]
+        #[case("90s", chrono::TimeDelta::seconds(90))]
+        #[case("1h30m", chrono::TimeDelta::minutes(90))]
+        #[case("-5m", chrono::TimeDelta::minutes(-5))]
+        #[case("250ms", chrono::TimeDelta::milliseconds(250))]
+        fn parse_time_delta_valid(#[case] input: &str, #[case] expected: chrono::TimeDelta) {
+            assert_eq!(chrono::TimeDelta::from_env_str(input).unwrap_or_else(|err| panic!("{err}")), expected);
+        }
+
+        #[test]
+        fn chrono_duration_alias_parses() {
+            assert_eq!(chrono::Duration::from_env_str("2h").unwrap_or_else(|err| panic!("{err}")), chrono::Duration::hours(2));
+        }
+
+        #[test]
+        fn parse_time_delta_error_names_token() {
+            let err = chrono::TimeDelta::from_env_str("-5q")
+                .err()
+                .unwrap_or_else(|| panic!("expected an error"))
+                .to_string();
+            assert!(err.contains("unknown unit in '5q'"), "{err}");
+        }
+    }
+}
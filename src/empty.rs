@@ -0,0 +1,145 @@
+use std::ffi::OsString;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::error::{Error, Location};
+use crate::source::{Origin, Source};
+
+/// what a variable that is set to an empty string means
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyAs {
+    /// as if it weren't set: the cascade moves on to the next key, then the default
+    Unset,
+    /// the empty string itself, parsed like any other value
+    #[default]
+    Value,
+    /// an `Error::Invalid`
+    Error,
+}
+
+impl EmptyAs {
+    const fn to_u8(self) -> u8 {
+        match self {
+            Self::Unset => 0,
+            Self::Value => 1,
+            Self::Error => 2,
+        }
+    }
+
+    const fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Unset,
+            2 => Self::Error,
+            _ => Self::Value,
+        }
+    }
+}
+
+static EMPTY_AS: AtomicU8 = AtomicU8::new(EmptyAs::Value.to_u8());
+
+/// the policy for sources that don't set their own, `EmptyAs::Value` unless changed
+pub fn empty_as() -> EmptyAs {
+    EmptyAs::from_u8(EMPTY_AS.load(Ordering::Relaxed))
+}
+
+/// change the process-wide policy, e.g. to `EmptyAs::Unset` for containers that inject `PORT=""`
+pub fn set_empty_as(policy: EmptyAs) {
+    EMPTY_AS.store(policy.to_u8(), Ordering::Relaxed);
+}
+
+/// the raw value of `key`, with an empty one left out under `EmptyAs::Unset`
+pub(crate) fn get_raw(source: &dyn Source, key: &str) -> Option<OsString> {
+    source
+        .get_os(key)
+        .filter(|raw| !raw.is_empty() || source.empty_as() != EmptyAs::Unset)
+}
+
+/// like `get_raw`, but an empty value is an error under `EmptyAs::Error`
+pub(crate) fn get_checked(source: &dyn Source, key: &str) -> crate::Result<Option<OsString>> {
+    match get_raw(source, key) {
+        Some(raw) if raw.is_empty() && source.empty_as() == EmptyAs::Error => Err(Error::Invalid {
+            key: source.key_name(key),
+            got: String::new(),
+            reason: "must not be empty".to_owned(),
+            location: Location::default(),
+        }),
+        raw => Ok(raw),
+    }
+}
+
+/// `source` read under a different policy
+pub(crate) struct WithEmptyAs<'a> {
+    pub(crate) source: &'a dyn Source,
+    pub(crate) policy: EmptyAs,
+}
+
+impl Source for WithEmptyAs<'_> {
+    fn get(&self, key: &str) -> Option<String> {
+        self.source.get(key)
+    }
+
+    fn get_os(&self, key: &str) -> Option<OsString> {
+        self.source.get_os(key)
+    }
+
+    fn origin(&self, key: &str) -> Origin {
+        self.source.origin(key)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.source.keys()
+    }
+
+    fn empty_as(&self) -> EmptyAs {
+        self.policy
+    }
+
+    fn key_name(&self, key: &str) -> String {
+        self.source.key_name(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(EmptyAs::Unset)]
+    #[case(EmptyAs::Value)]
+    #[case(EmptyAs::Error)]
+    fn policy_round_trips_through_u8(#[case] policy: EmptyAs) {
+        assert_eq!(EmptyAs::from_u8(policy.to_u8()), policy);
+    }
+
+    #[test]
+    fn default_policy_keeps_empty_values() {
+        assert_eq!(EmptyAs::default(), EmptyAs::Value);
+        let map = HashMap::from([("PORT", "")]);
+        assert_eq!(get_checked(&map, "PORT").unwrap_or_else(|err| panic!("{err}")), Some(OsString::new()));
+    }
+
+    #[rstest]
+    #[case(EmptyAs::Unset, "", None)]
+    #[case(EmptyAs::Unset, "80", Some("80"))]
+    #[case(EmptyAs::Value, "", Some(""))]
+    #[case(EmptyAs::Error, "80", Some("80"))]
+    fn policy_applies_to_empty_values(#[case] policy: EmptyAs, #[case] raw: &str, #[case] expected: Option<&str>) {
+        let map = HashMap::from([("PORT", raw)]);
+        let source = WithEmptyAs { source: &map, policy };
+        assert_eq!(get_checked(&source, "PORT").unwrap_or_else(|err| panic!("{err}")), expected.map(OsString::from));
+    }
+
+    #[test]
+    fn error_policy_rejects_empty_values() {
+        let map = HashMap::from([("PORT", "")]);
+        let source = WithEmptyAs { source: &map, policy: EmptyAs::Error };
+        let err = get_checked(&source, "PORT")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Invalid { ref key, ref reason, .. } if key == "PORT" && reason == "must not be empty"), "{err}");
+        assert_eq!(get_raw(&source, "PORT"), Some(OsString::new()));
+    }
+}
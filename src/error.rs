@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use crate::source::Origin;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[non_exhaustive]
@@ -24,34 +26,167 @@ impl std::fmt::Display for Location {
 #[non_exhaustive]
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("{location}none of [{keys}] found in environment")]
-    NotFound { keys: String, location: Location },
+    #[error("{location}none of [{keys}] found in environment{}", did_you_mean(suggestions))]
+    #[non_exhaustive]
+    NotFound {
+        keys: String,
+        /// set keys that look like a typo of one of `keys`, closest first
+        suggestions: Vec<String>,
+        location: Location,
+    },
 
-    #[error("{location}{key}: expected {expected}, got '{got}': {source}")]
+    #[error("{location}{key}{origin}: expected {expected}, got '{got}': {source}")]
+    #[non_exhaustive]
     Parse {
         key: String,
         expected: &'static str,
         got: String,
         source: Box<dyn std::error::Error + Send + Sync>,
+        origin: Box<Origin>,
         location: Location,
     },
 
+    #[error("{location}{key}: '{got}' is invalid: {reason}")]
+    #[non_exhaustive]
+    Invalid { key: String, got: String, reason: String, location: Location },
+
+    #[error("{location}{key}: value is not valid unicode")]
+    #[non_exhaustive]
+    NotUnicode { key: String, location: Location },
+
+    #[error("{location}{key} is deprecated{}", replacement.as_ref().map(|name| format!(", use {name} instead")).unwrap_or_default())]
+    #[non_exhaustive]
+    Deprecated { key: String, replacement: Option<String>, location: Location },
+
+    #[error("{location}{key} is ambiguous: [{}] all match", matches.join(", "))]
+    #[non_exhaustive]
+    AmbiguousKey { key: String, matches: Vec<String>, location: Location },
+
     #[error("failed to load dotenv from {path}: {source}")]
+    #[non_exhaustive]
     DotenvLoad { path: PathBuf, source: std::io::Error },
 
-    #[error("{path}:{line}: {message}")]
-    DotenvParse { path: PathBuf, line: usize, message: String },
+    #[error("{}:{line}{}: {message}", path.display(), column.map(|column| format!(":{column}")).unwrap_or_default())]
+    #[non_exhaustive]
+    DotenvParse { path: PathBuf, line: usize, column: Option<usize>, message: String },
+
+    #[error("{0}")]
+    Multiple(Errors),
 }
 
 impl Error {
     pub fn with_location(self, file: &'static str, line: u32) -> Self {
         let location = Location { file, line };
         match self {
-            Self::NotFound { keys, .. } => Self::NotFound { keys, location },
-            Self::Parse { key, expected, got, source, .. } => Self::Parse { key, expected, got, source, location },
+            Self::NotFound { keys, suggestions, .. } => Self::NotFound { keys, suggestions, location },
+            Self::NotUnicode { key, .. } => Self::NotUnicode { key, location },
+            Self::Invalid { key, got, reason, .. } => Self::Invalid { key, got, reason, location },
+            Self::AmbiguousKey { key, matches, .. } => Self::AmbiguousKey { key, matches, location },
+            Self::Deprecated { key, replacement, .. } => Self::Deprecated { key, replacement, location },
+            Self::Parse {
+                key, expected, got, source, origin, ..
+            } => Self::Parse {
+                key,
+                expected,
+                got,
+                source,
+                origin,
+                location,
+            },
             other => other,
         }
     }
+
+    /// mask the raw value and replace the source, which may echo it
+    pub fn redacted(self) -> Self {
+        match self {
+            Self::Parse {
+                key, expected, got, origin, location, ..
+            } => Self::Parse {
+                key,
+                expected,
+                got: crate::secret::mask(&got),
+                source: Box::new(crate::secret::RedactedError::new(expected)),
+                origin,
+                location,
+            },
+            Self::Invalid { key, got, reason, location } => Self::Invalid {
+                key,
+                got: crate::secret::mask(&got),
+                reason,
+                location,
+            },
+            other => other,
+        }
+    }
+}
+
+/// `; did you mean A, B or C?`, or nothing without suggestions
+fn did_you_mean(suggestions: &[String]) -> String {
+    match suggestions {
+        [] => String::new(),
+        [only] => format!("; did you mean {only}?"),
+        [rest @ .., last] => format!("; did you mean {} or {last}?", rest.join(", ")),
+    }
+}
+
+/// several errors reported together, usually gathered by a `Collector`
+#[derive(Debug, Default)]
+pub struct Errors {
+    errors: Vec<Error>,
+}
+
+impl Errors {
+    pub fn new(errors: Vec<Error>) -> Self {
+        Self { errors }
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.errors.iter()
+    }
+
+    pub fn into_vec(self) -> Vec<Error> {
+        self.errors
+    }
+}
+
+impl std::fmt::Display for Errors {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let noun = if self.errors.len() == 1 { "error" } else { "errors" };
+        write!(formatter, "{} configuration {noun}:", self.errors.len())?;
+        for err in &self.errors {
+            write!(formatter, "\n  {err}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Errors {}
+
+impl IntoIterator for Errors {
+    type Item = Error;
+    type IntoIter = std::vec::IntoIter<Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Errors {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
 }
 
 #[cfg(test)]
@@ -62,6 +197,7 @@ mod tests {
     fn not_found_without_location() {
         let err = Error::NotFound {
             keys: "FOO, BAR".into(),
+            suggestions: Vec::new(),
             location: Location::default(),
         };
         assert_eq!(err.to_string(), "none of [FOO, BAR] found in environment");
@@ -71,6 +207,7 @@ mod tests {
     fn not_found_with_location() {
         let err = Error::NotFound {
             keys: "PORT".into(),
+            suggestions: Vec::new(),
             location: Location::default(),
         }
         .with_location("src/config.rs", 42);
@@ -86,6 +223,7 @@ mod tests {
             expected: "u16",
             got: "banana".into(),
             source: "invalid digit found in string".into(),
+            origin: Box::default(),
             location: Location::default(),
         }
         .with_location("src/main.rs", 10);
@@ -103,6 +241,7 @@ mod tests {
             expected: "u16",
             got: "banana".into(),
             source: "invalid digit found in string".into(),
+            origin: Box::default(),
             location: Location::default(),
         };
         let msg = err.to_string();
@@ -124,6 +263,7 @@ mod tests {
         let err = Error::DotenvParse {
             path: PathBuf::from("/tmp/.env"),
             line: 3,
+            column: None,
             message: "missing = in assignment".into(),
         };
         let msg = err.to_string();
@@ -147,4 +287,115 @@ mod tests {
         assert_eq!(loc.file, "src/main.rs");
         assert_eq!(loc.line, 42);
     }
+
+    #[test]
+    fn parse_displays_origin() {
+        let err = Error::Parse {
+            key: "PORT".into(),
+            expected: "u16",
+            got: "banana".into(),
+            source: "invalid digit found in string".into(),
+            origin: Box::new(Origin::new(".env.local").with_line(4)),
+            location: Location::default(),
+        };
+        assert!(err.to_string().starts_with("PORT (from .env.local line 4): expected u16"));
+    }
+
+    #[test]
+    fn errors_display_lists_every_error() {
+        let errors = Errors::new(vec![
+            Error::NotFound {
+                keys: "DATABASE_URL".into(),
+                suggestions: Vec::new(),
+                location: Location::default(),
+            }
+            .with_location("src/config.rs", 14),
+            Error::Parse {
+                key: "PORT".into(),
+                expected: "u16",
+                got: "banana".into(),
+                source: "invalid digit found in string".into(),
+                origin: Box::default(),
+                location: Location::default(),
+            }
+            .with_location("src/config.rs", 15),
+        ]);
+        let msg = errors.to_string();
+        let lines: Vec<&str> = msg.lines().collect();
+        assert_eq!(lines[0], "2 configuration errors:");
+        assert_eq!(lines[1], "  src/config.rs:14: none of [DATABASE_URL] found in environment");
+        assert!(lines[2].starts_with("  src/config.rs:15: PORT: expected u16, got 'banana'"));
+    }
+
+    #[test]
+    fn errors_display_singular() {
+        let errors = Errors::new(vec![Error::NotFound {
+            keys: "PORT".into(),
+            suggestions: Vec::new(),
+            location: Location::default(),
+        }]);
+        assert!(errors.to_string().starts_with("1 configuration error:"));
+    }
+
+    #[test]
+    fn redacted_masks_value_and_source() {
+        let err = Error::Parse {
+            key: "API_TOKEN".into(),
+            expected: "bool",
+            got: "hunter2".into(),
+            source: "cannot parse 'hunter2' as boolean".into(),
+            origin: Box::default(),
+            location: Location::default(),
+        }
+        .redacted()
+        .with_location("src/main.rs", 3);
+        let msg = err.to_string();
+        assert!(!msg.contains("hunter2"), "{msg}");
+        assert!(msg.starts_with("src/main.rs:3: API_TOKEN: expected bool, got '**** (7 chars)'"), "{msg}");
+    }
+
+    #[test]
+    fn redacted_passes_through_not_found() {
+        let err = Error::NotFound {
+            keys: "API_TOKEN".into(),
+            suggestions: Vec::new(),
+            location: Location::default(),
+        }
+        .redacted();
+        assert!(matches!(err, Error::NotFound { .. }));
+    }
+
+    #[test]
+    fn not_unicode_with_location() {
+        let err = Error::NotUnicode {
+            key: "DATA_DIR".into(),
+            location: Location::default(),
+        }
+        .with_location("src/main.rs", 7);
+        assert_eq!(err.to_string(), "src/main.rs:7: DATA_DIR: value is not valid unicode");
+    }
+
+    #[test]
+    fn dotenv_parse_displays_column() {
+        let err = Error::DotenvParse {
+            path: PathBuf::from("/tmp/.env"),
+            line: 3,
+            column: Some(7),
+            message: "unterminated \" quote".into(),
+        };
+        assert_eq!(err.to_string(), "/tmp/.env:3:7: unterminated \" quote");
+    }
+
+    #[rstest::rstest]
+    #[case(&[], "none of [DATABASE_URL] found in environment")]
+    #[case(&["DATABSE_URL"], "none of [DATABASE_URL] found in environment; did you mean DATABSE_URL?")]
+    #[case(&["A", "B", "C"], "none of [DATABASE_URL] found in environment; did you mean A, B or C?")]
+    fn not_found_displays_suggestions(#[case] suggestions: &[&str], #[case] expected: &str) {
+        let err = Error::NotFound {
+            keys: "DATABASE_URL".into(),
+            suggestions: suggestions.iter().map(|key| (*key).to_owned()).collect(),
+            location: Location::default(),
+        };
+        assert_eq!(err.to_string(), expected);
+    }
 }
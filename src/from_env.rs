@@ -0,0 +1,127 @@
+use crate::source::{Env, Source};
+
+/// a whole config struct resolved in one go; usually derived with `#[derive(FromEnv)]`
+pub trait FromEnv: Sized {
+    fn from_source(source: &dyn Source) -> crate::error::Result<Self>;
+
+    fn from_env() -> crate::error::Result<Self> {
+        Self::from_source(&Env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use environs_derive::FromEnv;
+
+    use super::FromEnv;
+    use crate::error::Error;
+
+    fn default_workers() -> usize {
+        4
+    }
+
+    #[derive(Debug, FromEnv)]
+    struct Config {
+        #[env("APP_PORT", "PORT", default = 8080)]
+        port: u16,
+        #[env("DATABASE_URL")]
+        database_url: String,
+        #[env("DEBUG")]
+        debug: Option<bool>,
+        #[env("TIMEOUT_SECS", default_str = "30")]
+        timeout_secs: u64,
+        #[env("WORKERS", default_fn = default_workers)]
+        workers: usize,
+        #[env("HOSTS", resolve_with = |raw: &str| -> Result<Vec<String>, std::convert::Infallible> { Ok(raw.split(':').map(str::to_owned).collect()) })]
+        hosts: Vec<String>,
+        log_level: String,
+    }
+
+    fn source(pairs: &[(&'static str, &'static str)]) -> HashMap<&'static str, &'static str> {
+        pairs.iter().copied().collect()
+    }
+
+    #[test]
+    fn derive_resolves_every_field() {
+        let map = source(&[
+            ("PORT", "3000"),
+            ("DATABASE_URL", "postgres://localhost/app"),
+            ("DEBUG", "yes"),
+            ("TIMEOUT_SECS", "5"),
+            ("WORKERS", "8"),
+            ("HOSTS", "a:b"),
+            ("LOG_LEVEL", "info"),
+        ]);
+        let config = Config::from_source(&map).unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(config.port, 3000);
+        assert_eq!(config.database_url, "postgres://localhost/app");
+        assert_eq!(config.debug, Some(true));
+        assert_eq!(config.timeout_secs, 5);
+        assert_eq!(config.workers, 8);
+        assert_eq!(config.hosts, vec!["a".to_owned(), "b".to_owned()]);
+        assert_eq!(config.log_level, "info");
+    }
+
+    #[test]
+    fn derive_applies_defaults() {
+        let map = source(&[("DATABASE_URL", "postgres://localhost/app"), ("HOSTS", "a"), ("LOG_LEVEL", "debug")]);
+        let config = Config::from_source(&map).unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.debug, None);
+        assert_eq!(config.timeout_secs, 30);
+        assert_eq!(config.workers, 4);
+    }
+
+    #[test]
+    fn derive_missing_required_field_is_not_found() {
+        let map = source(&[("HOSTS", "a"), ("LOG_LEVEL", "debug")]);
+        let err = Config::from_source(&map).err().unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref keys, .. } if keys == "DATABASE_URL"), "{err}");
+        assert!(err.to_string().contains("from_env.rs:"), "{err}");
+    }
+
+    #[test]
+    fn derive_parse_error_names_key() {
+        let map = source(&[("APP_PORT", "banana"), ("DATABASE_URL", "x"), ("HOSTS", "a"), ("LOG_LEVEL", "debug")]);
+        let err = Config::from_source(&map).err().unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Parse { ref key, .. } if key == "APP_PORT"), "{err}");
+    }
+
+    #[test]
+    fn derive_passes_modifiers_to_var() {
+        #[derive(FromEnv)]
+        struct Secrets {
+            #[env("API_TOKEN", sensitive)]
+            _token: u32,
+        }
+        let map = source(&[("API_TOKEN", "hunter2")]);
+        let err = Secrets::from_source(&map).err().map(|err| err.to_string()).unwrap_or_default();
+        assert!(err.contains("API_TOKEN"), "{err}");
+        assert!(!err.contains("hunter2"), "{err}");
+    }
+
+    #[test]
+    fn derive_from_env_reads_process_environment() {
+        #[derive(FromEnv)]
+        struct Small {
+            #[env("TEST_DERIVE_PORT")]
+            port: u16,
+        }
+        temp_env::with_vars([("TEST_DERIVE_PORT", Some("9000"))], || {
+            assert_eq!(Small::from_env().unwrap_or_else(|err| panic!("{err}")).port, 9000);
+        });
+    }
+
+    #[test]
+    fn derive_parsed_uses_from_str() {
+        #[derive(FromEnv)]
+        struct Workers {
+            #[env("WORKERS", parsed)]
+            count: std::num::NonZeroUsize,
+        }
+        let map = source(&[("WORKERS", "3")]);
+        assert_eq!(Workers::from_source(&map).unwrap_or_else(|err| panic!("{err}")).count.get(), 3);
+    }
+}
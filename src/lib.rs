@@ -1,14 +1,43 @@
+extern crate self as environs;
+
 mod builder;
+mod collect;
 mod dotenv;
+mod duration;
+mod empty;
 mod error;
+mod from_env;
 mod macros;
+mod nested;
+mod net;
+mod normalize;
+// the `f64_parsing` test reads `3.14`
+#[cfg_attr(test, allow(clippy::approx_constant))]
 mod parse;
 mod resolve;
+mod scope;
+mod secret;
+mod source;
+mod suggest;
 
-pub use crate::builder::{Var, VarOr, VarOrElse, VarOrStr};
-pub use crate::dotenv::{DotenvLoader, load, load_override, load_override_path, load_path};
-pub use crate::error::{Error, Location, Result};
+pub use crate::builder::{Checks, Var, VarOr, VarOrElse, VarOrStr};
+pub use crate::collect::Collector;
+pub use crate::dotenv::{DotenvEntry, DotenvLoader, DotenvMap, load, load_override, load_override_path, load_path, parse_path, parse_reader, parse_str};
+pub use crate::duration::DurationParseError;
+pub use crate::empty::{EmptyAs, empty_as, set_empty_as};
+pub use crate::error::{Error, Errors, Location, Result};
+pub use crate::from_env::FromEnv;
+pub use crate::nested::Nested;
+pub use crate::net::{HostPort, IpNet, NetParseError};
 #[cfg(feature = "chrono")]
 pub use crate::parse::ChronoParseError;
-pub use crate::parse::{BoolParseError, FromEnvStr, VecParseError};
-pub use crate::resolve::{resolve, resolve_or, resolve_or_else, resolve_or_parse, resolve_with};
+pub use crate::parse::{BoolParseError, ByteSize, ByteSizeParseError, EnumParseError, FromEnvStr, MapParseError, MapPart, Parsed, ParsedError, Separated, SeparatedMap, VecParseError};
+pub use crate::resolve::{
+    Resolved, resolve, resolve_from, resolve_normalized, resolve_normalized_from, resolve_or, resolve_or_else, resolve_or_else_from, resolve_or_from, resolve_or_parse, resolve_or_parse_from,
+    resolve_traced, resolve_traced_from, resolve_with, resolve_with_from,
+};
+pub use crate::scope::{Scope, scope};
+pub use crate::secret::{RedactedError, Secret};
+pub use crate::source::{Env, Layered, Origin, Prefixed, Source};
+#[cfg(feature = "derive")]
+pub use environs_derive::{FromEnv, FromEnvStr};
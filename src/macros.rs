@@ -1,23 +1,47 @@
+/// resolve a typed value from the environment, tagging errors with the caller's location
+///
+/// keys come first, then at most one of `default`, `default_str`, `default_fn`, `resolve_with` or
+/// `parsed` (for any `FromStr` type), then any `Var` modifiers such as `sensitive`,
+/// `prefix = "BILLING_"` or `scope = &billing`.
 #[macro_export]
 macro_rules! env {
-    ($($key:literal),+ , default_fn = $default:expr) => {
-        $crate::resolve_or_else(&[$($key),+], $default)
+    ($($key:literal),+ , default_fn = $default:expr $(, $modifier:ident $(= $arg:expr)?)* $(,)?) => {
+        $crate::Var::new(&[$($key),+])
+            $(.$modifier($($arg)?))*
+            .default_fn($default)
+            .get()
             .map_err(|err| err.with_location(file!(), line!()))
     };
-    ($($key:literal),+ , default_str = $default:expr) => {
-        $crate::resolve_or_parse(&[$($key),+], $default)
+    ($($key:literal),+ , default_str = $default:expr $(, $modifier:ident $(= $arg:expr)?)* $(,)?) => {
+        $crate::Var::new(&[$($key),+])
+            $(.$modifier($($arg)?))*
+            .default_str($default)
+            .get()
             .map_err(|err| err.with_location(file!(), line!()))
     };
-    ($($key:literal),+ , default = $default:expr) => {
-        $crate::resolve_or(&[$($key),+], $default)
+    ($($key:literal),+ , default = $default:expr $(, $modifier:ident $(= $arg:expr)?)* $(,)?) => {
+        $crate::Var::new(&[$($key),+])
+            $(.$modifier($($arg)?))*
+            .default($default)
+            .get()
             .map_err(|err| err.with_location(file!(), line!()))
     };
-    ($($key:literal),+ , resolve_with = $parse_fn:expr) => {
-        $crate::resolve_with(&[$($key),+], $parse_fn)
+    ($($key:literal),+ , resolve_with = $parse_fn:expr $(, $modifier:ident $(= $arg:expr)?)* $(,)?) => {
+        $crate::Var::new(&[$($key),+])
+            $(.$modifier($($arg)?))*
+            .resolve_with($parse_fn)
             .map_err(|err| err.with_location(file!(), line!()))
     };
-    ($($key:literal),+) => {
-        $crate::resolve(&[$($key),+])
+    ($($key:literal),+ , parsed $(, $modifier:ident $(= $arg:expr)?)* $(,)?) => {
+        $crate::Var::new(&[$($key),+])
+            $(.$modifier($($arg)?))*
+            .parsed()
+            .map_err(|err| err.with_location(file!(), line!()))
+    };
+    ($($key:literal),+ $(, $modifier:ident $(= $arg:expr)?)* $(,)?) => {
+        $crate::Var::new(&[$($key),+])
+            $(.$modifier($($arg)?))*
+            .get()
             .map_err(|err| err.with_location(file!(), line!()))
     };
 }
@@ -251,4 +275,114 @@ mod tests {
             assert_eq!(result.ok(), Some(99));
         });
     }
+
+    #[test]
+    fn sensitive_masks_value() {
+        temp_env::with_vars([("TEST_MACRO_SENSITIVE", Some("hunter2"))], || {
+            let result: crate::Result<u32> = env!("TEST_MACRO_SENSITIVE", sensitive);
+            let msg = result.unwrap_err().to_string();
+            assert!(msg.contains("macros.rs:"), "error should have source location: {msg}");
+            assert!(!msg.contains("hunter2"), "error should not contain raw value: {msg}");
+        });
+    }
+
+    #[test]
+    fn sensitive_with_default_str() {
+        temp_env::with_vars([("TEST_MACRO_SENSITIVE_DSTR", Some("hunter2"))], || {
+            let result: crate::Result<u32> = env!("TEST_MACRO_SENSITIVE_DSTR", default_str = "1", sensitive);
+            let msg = result.unwrap_err().to_string();
+            assert!(!msg.contains("hunter2"), "error should not contain raw value: {msg}");
+        });
+    }
+
+    #[test]
+    fn sensitive_cascade_found() {
+        temp_env::with_vars([("TEST_MACRO_SENSITIVE_A", None::<&str>), ("TEST_MACRO_SENSITIVE_B", Some("token"))], || {
+            let result: crate::Result<String> = env!("TEST_MACRO_SENSITIVE_A", "TEST_MACRO_SENSITIVE_B", sensitive);
+            assert_eq!(result.ok(), Some("token".to_owned()));
+        });
+    }
+
+    #[test]
+    fn schemes_modifier_rejects_other_scheme() {
+        temp_env::with_vars([("TEST_MACRO_SCHEMES", Some("mysql://db/app"))], || {
+            let result: crate::Result<String> = env!("TEST_MACRO_SCHEMES", schemes = &["postgres", "postgresql"]);
+            let msg = result.unwrap_err().to_string();
+            assert!(msg.contains("macros.rs:"), "error should have source location: {msg}");
+            assert!(msg.contains("scheme 'mysql'"), "{msg}");
+        });
+    }
+
+    #[test]
+    fn scope_modifier_prefixes_keys() {
+        temp_env::with_vars([("TEST_MACRO_SCOPE_PORT", Some("7000")), ("TEST_MACRO_SCOPE_HOST", None::<&str>)], || {
+            let billing = crate::scope("TEST_MACRO_SCOPE_");
+            let port: crate::Result<u16> = env!("PORT", default = 1, scope = &billing);
+            assert_eq!(port.ok(), Some(7000));
+            let host: crate::Result<String> = env!("HOST", scope = &billing);
+            let msg = host.unwrap_err().to_string();
+            assert!(msg.contains("[TEST_MACRO_SCOPE_HOST]"), "{msg}");
+        });
+    }
+
+    #[test]
+    fn prefix_and_or_keys_modifiers() {
+        temp_env::with_vars([("TEST_MACRO_PREFIX_PORT", None::<&str>), ("TEST_MACRO_PLAIN_PORT", Some("42"))], || {
+            let port: crate::Result<u16> = env!("PORT", prefix = "TEST_MACRO_PREFIX_", or_keys = &["TEST_MACRO_PLAIN_PORT"]);
+            assert_eq!(port.ok(), Some(42));
+        });
+    }
+
+    #[test]
+    fn normalize_keys_modifier() {
+        temp_env::with_vars([("test_macro_normalized-host", Some("db"))], || {
+            let host: crate::Result<String> = env!("TEST_MACRO_NORMALIZED_HOST", normalize_keys);
+            assert_eq!(host.ok().as_deref(), Some("db"));
+        });
+    }
+
+    #[test]
+    fn deprecated_modifier() {
+        temp_env::with_vars([("TEST_MACRO_NEW_URL", None::<&str>), ("TEST_MACRO_OLD_URL", Some("old"))], || {
+            let url: crate::Result<String> = env!("TEST_MACRO_NEW_URL", deprecated = ["TEST_MACRO_OLD_URL"]);
+            assert_eq!(url.ok().as_deref(), Some("old"));
+
+            let line = line!() + 1;
+            let url: crate::Result<String> = env!("TEST_MACRO_NEW_URL", deprecated = ["TEST_MACRO_OLD_URL"], deny_deprecated);
+            let err = url.unwrap_err();
+            assert!(
+                matches!(err, crate::Error::Deprecated { ref location, .. } if location.file == file!() && location.line == line),
+                "{err:?}"
+            );
+        });
+    }
+
+    #[test]
+    fn validation_modifiers() {
+        temp_env::with_vars([("TEST_MACRO_VALID_PORT", Some("0")), ("TEST_MACRO_VALID_LEVEL", Some("info"))], || {
+            let port: crate::Result<u16> = env!("TEST_MACRO_VALID_PORT", default = 80, range = 1..=65535);
+            let err = port.unwrap_err();
+            assert!(matches!(err, crate::Error::Invalid { ref location, .. } if location.file == file!()), "{err:?}");
+            let level: crate::Result<String> = env!("TEST_MACRO_VALID_LEVEL", one_of = &["debug", "info"], non_empty);
+            assert_eq!(level.ok().as_deref(), Some("info"));
+        });
+    }
+
+    #[test]
+    fn empty_as_modifier() {
+        temp_env::with_vars([("TEST_MACRO_EMPTY_A", Some("")), ("TEST_MACRO_EMPTY_B", Some("5"))], || {
+            let value: crate::Result<u8> = env!("TEST_MACRO_EMPTY_A", "TEST_MACRO_EMPTY_B", empty_as = crate::EmptyAs::Unset);
+            assert_eq!(value.ok(), Some(5));
+        });
+    }
+
+    #[test]
+    fn parsed_terminal() {
+        temp_env::with_vars([("TEST_MACRO_PARSED", Some("7"))], || {
+            let value: crate::Result<std::num::NonZeroU8> = env!("TEST_MACRO_PARSED", parsed);
+            assert_eq!(value.ok().map(std::num::NonZeroU8::get), Some(7));
+            let value: crate::Result<u8> = env!("TEST_MACRO_PARSED", parsed, range = 1..=5);
+            assert!(matches!(value, Err(crate::Error::Invalid { .. })), "{value:?}");
+        });
+    }
 }
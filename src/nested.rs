@@ -0,0 +1,321 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::error::{Error, Location};
+use crate::from_env::FromEnv;
+use crate::parse::FromEnvStr;
+use crate::resolve::resolve_from;
+use crate::source::{Env, Prefixed, Source};
+
+/// assembles lists, maps and nested structs from keys such as `HOSTS_0`, `SERVERS_1_PORT`
+/// or `DB__HOST` by scanning the source's keys
+///
+/// the separator between a prefix and what follows it defaults to `_`
+pub struct Nested<'a> {
+    source: &'a dyn Source,
+    separator: &'a str,
+}
+
+impl Default for Nested<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Nested<'a> {
+    pub fn new() -> Self {
+        Self::from_source(&Env)
+    }
+
+    pub fn from_source(source: &'a dyn Source) -> Self {
+        Self { source, separator: "_" }
+    }
+
+    /// the separator between a prefix and an index, map key or field, e.g. `__`
+    pub fn separator(mut self, separator: &'a str) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    fn child(&self, prefix: &str, name: impl std::fmt::Display) -> String {
+        format!("{prefix}{}{name}", self.separator)
+    }
+
+    /// what follows `{prefix}{separator}` in every key that has it
+    fn suffixes(&self, prefix: &str) -> Vec<String> {
+        let start = self.child(prefix, "");
+        self.source
+            .keys()
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&start).filter(|rest| !rest.is_empty()).map(str::to_owned))
+            .collect()
+    }
+
+    /// a leading segment and whether more segments follow it
+    fn split_segment<'s>(&self, suffix: &'s str) -> (&'s str, bool) {
+        match suffix.split_once(self.separator) {
+            Some((segment, rest)) => (segment, !rest.is_empty()),
+            None => (suffix, false),
+        }
+    }
+
+    /// indices used as `{prefix}{separator}{n}`, either as the whole key (`leaf`) or followed by fields
+    fn indices(&self, prefix: &str, leaf: bool) -> BTreeSet<usize> {
+        self.suffixes(prefix)
+            .iter()
+            .filter_map(|suffix| {
+                let (segment, nested) = self.split_segment(suffix);
+                let canonical = segment.len() == 1 || !segment.starts_with('0');
+                if nested == leaf || !canonical || !segment.bytes().all(|byte| byte.is_ascii_digit()) {
+                    return None;
+                }
+                segment.parse().ok()
+            })
+            .collect()
+    }
+
+    fn section_at<T: FromEnv>(&self, prefix: &str) -> crate::Result<T> {
+        let prefix = format!("{prefix}{}", self.separator);
+        T::from_source(&Prefixed::new(prefix, self.source))
+    }
+
+    /// one past the highest of `indices`; an index of `usize::MAX` is an error rather than an overflow
+    fn len(&self, prefix: &str, indices: &BTreeSet<usize>) -> crate::Result<usize> {
+        let Some(&last) = indices.last() else {
+            return Ok(0);
+        };
+        last.checked_add(1).ok_or_else(|| Error::Invalid {
+            key: self.source.key_name(&self.child(prefix, last)),
+            got: last.to_string(),
+            reason: "index is too large".to_owned(),
+            location: Location::default(),
+        })
+    }
+
+    /// `{prefix}_0`, `{prefix}_1`, ... as a list; a gap is reported as a missing key
+    pub fn list<T: FromEnvStr>(&self, prefix: &str) -> crate::Result<Vec<T>> {
+        let len = self.len(prefix, &self.indices(prefix, true))?;
+        (0..len)
+            .map(|index| resolve_from(self.source, &[&self.child(prefix, index)]))
+            .collect()
+    }
+
+    /// `{prefix}_0_{FIELD}`, `{prefix}_1_{FIELD}`, ... as a list of structs
+    pub fn list_of<T: FromEnv>(&self, prefix: &str) -> crate::Result<Vec<T>> {
+        let indices = self.indices(prefix, false);
+        let len = self.len(prefix, &indices)?;
+        (0..len)
+            .map(|index| {
+                let item = self.child(prefix, index);
+                if !indices.contains(&index) {
+                    return Err(Error::NotFound {
+                        keys: self.source.key_name(&self.child(&item, "*")),
+                        suggestions: Vec::new(),
+                        location: Location::default(),
+                    });
+                }
+                self.section_at(&item)
+            })
+            .collect()
+    }
+
+    /// `{prefix}_{name}` for every name as a map; names keep their spelling
+    pub fn map<V: FromEnvStr>(&self, prefix: &str) -> crate::Result<BTreeMap<String, V>> {
+        self.suffixes(prefix)
+            .into_iter()
+            .map(|name| {
+                let value = resolve_from(self.source, &[&self.child(prefix, &name)])?;
+                Ok((name, value))
+            })
+            .collect()
+    }
+
+    /// `{prefix}_{name}_{FIELD}` for every name as a map of structs
+    pub fn map_of<T: FromEnv>(&self, prefix: &str) -> crate::Result<BTreeMap<String, T>> {
+        let names: BTreeSet<String> = self
+            .suffixes(prefix)
+            .iter()
+            .filter_map(|suffix| match self.split_segment(suffix) {
+                (segment, true) if !segment.is_empty() => Some(segment.to_owned()),
+                _ => None,
+            })
+            .collect();
+        names
+            .into_iter()
+            .map(|name| {
+                let value = self.section_at(&self.child(prefix, &name))?;
+                Ok((name, value))
+            })
+            .collect()
+    }
+
+    /// a struct whose fields are read as `{prefix}_{FIELD}`
+    pub fn section<T: FromEnv>(&self, prefix: &str) -> crate::Result<T> {
+        self.section_at(prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use environs_derive::FromEnv;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, FromEnv)]
+    struct Server {
+        host: String,
+        #[env("PORT", default = 80)]
+        port: u16,
+    }
+
+    fn server(host: &str, port: u16) -> Server {
+        Server { host: host.to_owned(), port }
+    }
+
+    #[test]
+    fn list_from_numbered_keys() {
+        let map = HashMap::from([("HOSTS_1", "b"), ("HOSTS_0", "a"), ("HOSTS_2", "c"), ("HOSTSX_3", "x")]);
+        let hosts: Vec<String> = Nested::from_source(&map).list("HOSTS").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(hosts, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn list_is_empty_without_keys() {
+        let map = HashMap::from([("OTHER", "1")]);
+        assert!(
+            Nested::from_source(&map)
+                .list::<String>("HOSTS")
+                .unwrap_or_else(|err| panic!("{err}"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn list_gap_reports_full_key() {
+        let map = HashMap::from([("HOSTS_0", "a"), ("HOSTS_2", "c")]);
+        let err = Nested::from_source(&map)
+            .list::<String>("HOSTS")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref keys, .. } if keys == "HOSTS_1"), "{err}");
+    }
+
+    #[test]
+    fn list_parse_error_reports_full_key() {
+        let map = HashMap::from([("PORTS_0", "80"), ("PORTS_1", "http")]);
+        let err = Nested::from_source(&map)
+            .list::<u16>("PORTS")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Parse { ref key, .. } if key == "PORTS_1"), "{err}");
+    }
+
+    #[test]
+    fn list_of_structs() {
+        let map = HashMap::from([("SERVERS_0_HOST", "a"), ("SERVERS_0_PORT", "8080"), ("SERVERS_1_HOST", "b")]);
+        let servers: Vec<Server> = Nested::from_source(&map)
+            .list_of("SERVERS")
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(servers, vec![server("a", 8080), server("b", 80)]);
+    }
+
+    #[test]
+    fn list_of_structs_reports_full_key() {
+        let map = HashMap::from([("SERVERS_0_HOST", "a"), ("SERVERS_1_PORT", "http")]);
+        let err = Nested::from_source(&map)
+            .list_of::<Server>("SERVERS")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref keys, .. } if keys == "SERVERS_1_HOST"), "{err}");
+
+        let map = HashMap::from([("SERVERS_0_HOST", "a"), ("SERVERS_1_HOST", "b"), ("SERVERS_1_PORT", "http")]);
+        let err = Nested::from_source(&map)
+            .list_of::<Server>("SERVERS")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::Parse { ref key, .. } if key == "SERVERS_1_PORT"), "{err}");
+    }
+
+    #[test]
+    fn highest_index_is_rejected() {
+        let map = HashMap::from([("HOSTS_18446744073709551615", "a"), ("SERVERS_18446744073709551615_HOST", "b")]);
+        let nested = Nested::from_source(&map);
+        let list = nested
+            .list::<String>("HOSTS")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(
+            matches!(list, Error::Invalid { ref key, ref reason, .. } if key == "HOSTS_18446744073709551615" && reason == "index is too large"),
+            "{list}"
+        );
+        let list_of = nested
+            .list_of::<Server>("SERVERS")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(list_of, Error::Invalid { ref key, .. } if key == "SERVERS_18446744073709551615"), "{list_of}");
+    }
+
+    #[test]
+    fn list_of_gap_is_reported() {
+        let map = HashMap::from([("SERVERS_0_HOST", "a"), ("SERVERS_2_HOST", "c")]);
+        let err = Nested::from_source(&map)
+            .list_of::<Server>("SERVERS")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref keys, .. } if keys == "SERVERS_1_*"), "{err}");
+    }
+
+    #[test]
+    fn map_with_double_underscore() {
+        let map = HashMap::from([("LABELS__team", "core"), ("LABELS__tier", "web"), ("LABELS", "ignored")]);
+        let labels: BTreeMap<String, String> = Nested::from_source(&map)
+            .separator("__")
+            .map("LABELS")
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(
+            labels.into_iter().collect::<Vec<_>>(),
+            vec![("team".to_owned(), "core".to_owned()), ("tier".to_owned(), "web".to_owned())]
+        );
+    }
+
+    #[test]
+    fn map_of_structs() {
+        let map = HashMap::from([("DB__primary__HOST", "p"), ("DB__replica__HOST", "r"), ("DB__replica__PORT", "5433")]);
+        let dbs: BTreeMap<String, Server> = Nested::from_source(&map)
+            .separator("__")
+            .map_of("DB")
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(dbs["primary"], server("p", 80));
+        assert_eq!(dbs["replica"], server("r", 5433));
+    }
+
+    #[test]
+    fn section_reads_nested_struct() {
+        let map = HashMap::from([("APP__DB__HOST", "db"), ("APP__DB__PORT", "5432")]);
+        let db: Server = Nested::from_source(&map)
+            .separator("__")
+            .section("APP__DB")
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(db, server("db", 5432));
+    }
+
+    #[test]
+    fn section_missing_field_reports_full_key() {
+        let map = HashMap::from([("DB__PORT", "5432")]);
+        let err = Nested::from_source(&map)
+            .separator("__")
+            .section::<Server>("DB")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref keys, .. } if keys == "DB__HOST"), "{err}");
+    }
+
+    #[test]
+    fn reads_process_environment() {
+        temp_env::with_vars([("TEST_NESTED_HOSTS_0", Some("a")), ("TEST_NESTED_HOSTS_1", Some("b"))], || {
+            let hosts: Vec<String> = Nested::new().list("TEST_NESTED_HOSTS").unwrap_or_else(|err| panic!("{err}"));
+            assert_eq!(hosts, vec!["a", "b"]);
+        });
+    }
+}
@@ -0,0 +1,115 @@
+use crate::empty::get_raw;
+use crate::error::{Error, Location};
+use crate::source::Source;
+
+/// `key` uppercased, with `-` and `.` read as `_`; `database-url` and `Database.Url` both become `DATABASE_URL`
+pub(crate) fn normalize(key: &str) -> String {
+    key.chars()
+        .flat_map(char::to_uppercase)
+        .map(|ch| if matches!(ch, '-' | '.') { '_' } else { ch })
+        .collect()
+}
+
+/// the spelling of `key` that `source` has set, if any. an exact match always wins; otherwise
+/// every key that normalizes the same way is a candidate, and more than one is an error
+fn match_key(source: &dyn Source, key: &str) -> crate::Result<Option<String>> {
+    if get_raw(source, key).is_some() {
+        return Ok(Some(key.to_owned()));
+    }
+    let wanted = normalize(key);
+    let mut candidates: Vec<String> = source
+        .keys()
+        .into_iter()
+        .filter(|candidate| normalize(candidate) == wanted && get_raw(source, candidate).is_some())
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    match candidates.len() {
+        0 => Ok(None),
+        1 => {
+            let found = candidates.swap_remove(0);
+            tracing::warn!(key, found, "environment variable matched by a non-canonical spelling");
+            Ok(Some(found))
+        }
+        _ => Err(Error::AmbiguousKey {
+            key: source.key_name(key),
+            matches: candidates.iter().map(|found| source.key_name(found)).collect(),
+            location: Location::default(),
+        }),
+    }
+}
+
+/// `keys` with the first one that is set replaced by its spelling in `source`; keys that
+/// aren't set keep theirs so a missing variable is reported under the canonical name
+pub(crate) fn match_keys(source: &dyn Source, keys: &[&str]) -> crate::Result<Vec<String>> {
+    let mut matched = Vec::with_capacity(keys.len());
+    for (index, key) in keys.iter().enumerate() {
+        if let Some(found) = match_key(source, key)? {
+            matched.push(found);
+            matched.extend(keys[index + 1..].iter().map(|key| (*key).to_owned()));
+            break;
+        }
+        matched.push((*key).to_owned());
+    }
+    Ok(matched)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("DATABASE_URL", "DATABASE_URL")]
+    #[case("database_url", "DATABASE_URL")]
+    #[case("Database-Url", "DATABASE_URL")]
+    #[case("database.url", "DATABASE_URL")]
+    #[case("a-b.c_d", "A_B_C_D")]
+    fn normalize_keys(#[case] key: &str, #[case] expected: &str) {
+        assert_eq!(normalize(key), expected);
+    }
+
+    #[test]
+    fn exact_spelling_wins() {
+        let map = HashMap::from([("DATABASE_URL", "a"), ("database_url", "b"), ("Database-Url", "c")]);
+        assert_eq!(match_keys(&map, &["DATABASE_URL"]).unwrap_or_else(|err| panic!("{err}")), vec!["DATABASE_URL"]);
+    }
+
+    #[test]
+    fn other_spelling_is_matched() {
+        let map = HashMap::from([("Database-Url", "a")]);
+        assert_eq!(match_keys(&map, &["DATABASE_URL"]).unwrap_or_else(|err| panic!("{err}")), vec!["Database-Url"]);
+    }
+
+    #[test]
+    fn unset_keys_keep_their_spelling() {
+        let map = HashMap::from([("port", "1")]);
+        assert_eq!(
+            match_keys(&map, &["APP_PORT", "PORT", "HTTP_PORT"]).unwrap_or_else(|err| panic!("{err}")),
+            vec!["APP_PORT", "port", "HTTP_PORT"]
+        );
+        assert_eq!(match_keys(&map, &["HOST"]).unwrap_or_else(|err| panic!("{err}")), vec!["HOST"]);
+    }
+
+    #[test]
+    fn several_spellings_are_ambiguous() {
+        let map = HashMap::from([("database_url", "a"), ("Database-Url", "b")]);
+        let err = match_keys(&map, &["DATABASE_URL"])
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(
+            matches!(err, Error::AmbiguousKey { ref key, ref matches, .. } if key == "DATABASE_URL" && matches == &["Database-Url", "database_url"]),
+            "{err}"
+        );
+        assert!(err.to_string().contains("[Database-Url, database_url]"), "{err}");
+    }
+
+    #[test]
+    fn later_keys_are_not_scanned_after_a_hit() {
+        let map = HashMap::from([("app_port", "1"), ("port", "2"), ("Port", "3")]);
+        assert_eq!(match_keys(&map, &["APP_PORT", "PORT"]).unwrap_or_else(|err| panic!("{err}")), vec!["app_port", "PORT"]);
+    }
+}
@@ -1,3 +1,7 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::hash::{BuildHasher, Hash};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
 use std::path::PathBuf;
 
 use falsehoods::Truthful;
@@ -10,9 +14,25 @@ pub trait FromEnvStr: Sized {
     fn on_not_found(keys: &[&str]) -> crate::error::Result<Self> {
         Err(crate::error::Error::NotFound {
             keys: keys.join(", "),
+            suggestions: Vec::new(),
             location: crate::error::Location::default(),
         })
     }
+
+    /// sensitive types have their raw value masked in parse errors
+    fn is_sensitive() -> bool {
+        false
+    }
+
+    /// how a raw value is shown in parse errors; types that embed credentials mask them here
+    fn redact_raw(raw: &str) -> String {
+        raw.to_owned()
+    }
+
+    /// parse a platform string; `None` when it isn't valid unicode and the type needs it to be
+    fn from_env_os(value: &OsStr) -> Option<std::result::Result<Self, Self::Err>> {
+        value.to_str().map(Self::from_env_str)
+    }
 }
 
 #[non_exhaustive]
@@ -47,7 +67,39 @@ impl FromEnvStr for bool {
     }
 }
 
-macro_rules! impl_from_env_str_numeric {
+/// a value that names none of an enum's variants; returned by `#[derive(FromEnvStr)]`
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct EnumParseError {
+    value: String,
+    type_name: &'static str,
+    variants: &'static [&'static str],
+}
+
+impl EnumParseError {
+    pub fn new(value: &str, type_name: &'static str, variants: &'static [&'static str]) -> Self {
+        Self {
+            value: value.to_owned(),
+            type_name,
+            variants,
+        }
+    }
+
+    /// the accepted names, without aliases
+    pub fn variants(&self) -> &'static [&'static str] {
+        self.variants
+    }
+}
+
+impl std::fmt::Display for EnumParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "cannot parse '{}' as {}: expected one of {}", self.value, self.type_name, self.variants.join(", "))
+    }
+}
+
+impl std::error::Error for EnumParseError {}
+
+macro_rules! impl_from_env_str_via_from_str {
     ($($typ:ty),+) => {
         $(
             impl FromEnvStr for $typ {
@@ -65,7 +117,8 @@ macro_rules! impl_from_env_str_numeric {
     };
 }
 
-impl_from_env_str_numeric!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+impl_from_env_str_via_from_str!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+impl_from_env_str_via_from_str!(IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6);
 
 impl FromEnvStr for String {
     type Err = std::convert::Infallible;
@@ -89,24 +142,167 @@ impl FromEnvStr for PathBuf {
     fn type_name() -> &'static str {
         "PathBuf"
     }
+
+    fn from_env_os(value: &OsStr) -> Option<std::result::Result<Self, Self::Err>> {
+        Some(Ok(PathBuf::from(value)))
+    }
+}
+
+impl FromEnvStr for OsString {
+    type Err = std::convert::Infallible;
+
+    fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(OsString::from(value))
+    }
+
+    fn type_name() -> &'static str {
+        "OsString"
+    }
+
+    fn from_env_os(value: &OsStr) -> Option<std::result::Result<Self, Self::Err>> {
+        Some(Ok(value.to_owned()))
+    }
+}
+
+/// a number of bytes, parsed from values like `512MiB`, `1.5GB` or `64k`
+///
+/// SI suffixes (`k`, `M`, `G`, `T`, `P`, `E`, optionally followed by `B`) are powers of 1000,
+/// IEC suffixes (`Ki`, `Mi`, ... optionally followed by `B`) powers of 1024; suffixes are
+/// case-insensitive and a bare number is bytes. fractions are rounded down to whole bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+#[derive(Debug)]
+enum ByteSizeProblem {
+    Empty,
+    InvalidNumber,
+    UnknownSuffix(String),
+    Overflow,
+}
+
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct ByteSizeParseError {
+    value: String,
+    problem: ByteSizeProblem,
+}
+
+impl std::fmt::Display for ByteSizeParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "cannot parse '{}' as byte size: ", self.value)?;
+        match &self.problem {
+            ByteSizeProblem::Empty => write!(formatter, "empty value"),
+            ByteSizeProblem::InvalidNumber => write!(formatter, "invalid number"),
+            ByteSizeProblem::UnknownSuffix(suffix) => write!(formatter, "unknown suffix '{suffix}'"),
+            ByteSizeProblem::Overflow => write!(formatter, "larger than {} bytes", u64::MAX),
+        }
+    }
+}
+
+impl std::error::Error for ByteSizeParseError {}
+
+fn byte_multiplier(suffix: &str) -> Option<u128> {
+    let lower = suffix.to_ascii_lowercase();
+    let unit = lower.strip_suffix('b').unwrap_or(&lower);
+    let (prefix, base) = match unit.strip_suffix('i') {
+        Some(prefix) if !prefix.is_empty() => (prefix, 1024u128),
+        _ => (unit, 1000u128),
+    };
+    let exponent = match prefix {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        "p" => 5,
+        "e" => 6,
+        _ => return None,
+    };
+    Some(base.pow(exponent))
+}
+
+impl FromEnvStr for ByteSize {
+    type Err = ByteSizeParseError;
+
+    fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let error = |problem| ByteSizeParseError { value: value.to_owned(), problem };
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            return Err(error(ByteSizeProblem::Empty));
+        }
+        let number_len = trimmed
+            .find(|ch: char| !(ch.is_ascii_digit() || ch == '.'))
+            .unwrap_or(trimmed.len());
+        let (number, suffix) = trimmed.split_at(number_len);
+        let suffix = suffix.trim_start();
+        let multiplier = byte_multiplier(suffix).ok_or_else(|| error(ByteSizeProblem::UnknownSuffix(suffix.to_owned())))?;
+
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if (whole.is_empty() && fraction.is_empty()) || fraction.contains('.') {
+            return Err(error(ByteSizeProblem::InvalidNumber));
+        }
+        let whole: u128 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| error(ByteSizeProblem::Overflow))? };
+        let mut bytes = whole.checked_mul(multiplier).ok_or_else(|| error(ByteSizeProblem::Overflow))?;
+        // the largest multiplier is below 10^19, so further digits never change the result
+        let fraction = &fraction[..fraction.len().min(19)];
+        if !fraction.is_empty() {
+            let digits: u128 = fraction.parse().map_err(|_| error(ByteSizeProblem::InvalidNumber))?;
+            bytes = bytes
+                .checked_add(digits * multiplier / 10u128.pow(fraction.len() as u32))
+                .ok_or_else(|| error(ByteSizeProblem::Overflow))?;
+        }
+        u64::try_from(bytes).map(ByteSize).map_err(|_| error(ByteSizeProblem::Overflow))
+    }
+
+    fn type_name() -> &'static str {
+        "byte size"
+    }
+}
+
+#[derive(Debug)]
+enum VecProblem {
+    Element(Box<dyn std::error::Error + Send + Sync>),
+    UnterminatedQuote(char),
+    AfterQuote,
+    Length { expected: usize, got: usize },
 }
 
 #[non_exhaustive]
 #[derive(Debug)]
 pub struct VecParseError {
     index: usize,
-    source: Box<dyn std::error::Error + Send + Sync>,
+    problem: VecProblem,
 }
 
 impl std::fmt::Display for VecParseError {
     fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(formatter, "element {}: {}", self.index, self.source)
+        match &self.problem {
+            VecProblem::Element(source) => write!(formatter, "element {}: {source}", self.index),
+            VecProblem::UnterminatedQuote(quote) => write!(formatter, "element {}: unterminated {quote} quote", self.index),
+            VecProblem::AfterQuote => write!(formatter, "element {}: unexpected content after closing quote", self.index),
+            VecProblem::Length { expected, got } => write!(formatter, "expected {expected} elements, got {got}"),
+        }
     }
 }
 
 impl std::error::Error for VecParseError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        Some(&*self.source)
+        match &self.problem {
+            VecProblem::Element(source) => Some(&**source),
+            _ => None,
+        }
     }
 }
 
@@ -124,6 +320,18 @@ impl<T: FromEnvStr> FromEnvStr for Option<T> {
     fn on_not_found(_keys: &[&str]) -> crate::error::Result<Self> {
         Ok(None)
     }
+
+    fn is_sensitive() -> bool {
+        T::is_sensitive()
+    }
+
+    fn redact_raw(raw: &str) -> String {
+        T::redact_raw(raw)
+    }
+
+    fn from_env_os(value: &OsStr) -> Option<std::result::Result<Self, Self::Err>> {
+        T::from_env_os(value).map(|parsed| parsed.map(Some))
+    }
 }
 
 #[cfg(feature = "chrono")]
@@ -255,22 +463,454 @@ mod chrono_impls {
     }
 }
 
-impl<T: FromEnvStr> FromEnvStr for Vec<T> {
+/// a quoted element's content and the text after its closing quote; backslash escapes only in `"`
+fn read_quoted(text: &str, quote: char) -> Option<(String, &str)> {
+    let mut element = String::new();
+    let mut chars = text.char_indices();
+    while let Some((index, ch)) = chars.next() {
+        match ch {
+            '\\' if quote == '"' => element.extend(chars.next().map(|(_, escaped)| escaped)),
+            ch if ch == quote => return Some((element, &text[index + ch.len_utf8()..])),
+            ch => element.push(ch),
+        }
+    }
+    None
+}
+
+/// split `value` into `(position, element)` pairs
+///
+/// elements are trimmed, and with `quotes` may be wrapped in `"` or `'` to contain the separator.
+/// a space separator splits on any run of whitespace. `skip_empty` drops unquoted empty elements.
+fn split_elements(value: &str, separator: char, skip_empty: bool, quotes: bool) -> std::result::Result<Vec<(usize, String)>, VecParseError> {
+    let whitespace = separator == ' ';
+    let is_separator = |ch: char| if whitespace { ch.is_whitespace() } else { ch == separator };
+    let is_padding = |ch: char| ch.is_whitespace() && !is_separator(ch);
+    let mut elements = Vec::new();
+    let mut rest = if whitespace { value.trim() } else { value };
+    if rest.is_empty() {
+        return Ok(elements);
+    }
+    for index in 0.. {
+        let error = |problem| VecParseError { index, problem };
+        let start = rest.trim_start_matches(is_padding);
+        let (element, quoted, after) = match start.chars().next() {
+            Some(quote @ ('"' | '\'')) if quotes => {
+                let (element, after) = read_quoted(&start[1..], quote).ok_or_else(|| error(VecProblem::UnterminatedQuote(quote)))?;
+                let after = after.trim_start_matches(is_padding);
+                if !(after.is_empty() || after.starts_with(is_separator)) {
+                    return Err(error(VecProblem::AfterQuote));
+                }
+                (element, true, after)
+            }
+            _ => {
+                let end = start.find(is_separator).unwrap_or(start.len());
+                (start[..end].trim().to_owned(), false, &start[end..])
+            }
+        };
+        if quoted || !(skip_empty && element.is_empty()) {
+            elements.push((index, element));
+        }
+        let mut chars = after.chars();
+        if chars.next().is_none() {
+            break;
+        }
+        rest = if whitespace { chars.as_str().trim_start() } else { chars.as_str() };
+    }
+    Ok(elements)
+}
+
+mod sealed {
+    use super::{FromEnvStr, VecParseError};
+
+    /// a collection built from separated elements; see `Separated`
+    pub trait Collection: Sized {
+        type Element: FromEnvStr;
+        const TYPE_NAME: &'static str;
+
+        fn from_elements(elements: Vec<Self::Element>) -> std::result::Result<Self, VecParseError>;
+    }
+}
+
+use sealed::Collection;
+
+fn parse_collection<C: Collection>(value: &str, separator: char, skip_empty: bool, quotes: bool) -> std::result::Result<C, VecParseError> {
+    let elements = split_elements(value, separator, skip_empty, quotes)?
+        .into_iter()
+        .map(|(index, element)| {
+            C::Element::from_env_str(&element).map_err(|source| VecParseError {
+                index,
+                problem: VecProblem::Element(Box::new(source)),
+            })
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    C::from_elements(elements)
+}
+
+impl<T: FromEnvStr> Collection for Vec<T> {
+    type Element = T;
+    const TYPE_NAME: &'static str = "Vec";
+
+    fn from_elements(elements: Vec<T>) -> std::result::Result<Self, VecParseError> {
+        Ok(elements)
+    }
+}
+
+impl<T: FromEnvStr> Collection for VecDeque<T> {
+    type Element = T;
+    const TYPE_NAME: &'static str = "VecDeque";
+
+    fn from_elements(elements: Vec<T>) -> std::result::Result<Self, VecParseError> {
+        Ok(elements.into())
+    }
+}
+
+impl<T: FromEnvStr + Eq + Hash, S: BuildHasher + Default> Collection for HashSet<T, S> {
+    type Element = T;
+    const TYPE_NAME: &'static str = "HashSet";
+
+    fn from_elements(elements: Vec<T>) -> std::result::Result<Self, VecParseError> {
+        Ok(elements.into_iter().collect())
+    }
+}
+
+impl<T: FromEnvStr + Ord> Collection for BTreeSet<T> {
+    type Element = T;
+    const TYPE_NAME: &'static str = "BTreeSet";
+
+    fn from_elements(elements: Vec<T>) -> std::result::Result<Self, VecParseError> {
+        Ok(elements.into_iter().collect())
+    }
+}
+
+impl<T: FromEnvStr, const N: usize> Collection for [T; N] {
+    type Element = T;
+    const TYPE_NAME: &'static str = "array";
+
+    fn from_elements(elements: Vec<T>) -> std::result::Result<Self, VecParseError> {
+        elements.try_into().map_err(|elements: Vec<T>| VecParseError {
+            index: elements.len().min(N),
+            problem: VecProblem::Length { expected: N, got: elements.len() },
+        })
+    }
+}
+
+/// implements `FromEnvStr` for a collection as plain comma-separated elements, quotes and all
+macro_rules! impl_from_env_str_collection {
+    ($([$($generic:tt)*] $typ:ty),+ $(,)?) => {
+        $(
+            impl<$($generic)*> FromEnvStr for $typ {
+                type Err = VecParseError;
+
+                fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
+                    parse_collection(value, ',', false, false)
+                }
+
+                fn type_name() -> &'static str {
+                    <Self as Collection>::TYPE_NAME
+                }
+
+                fn is_sensitive() -> bool {
+                    <Self as Collection>::Element::is_sensitive()
+                }
+
+                fn redact_raw(raw: &str) -> String {
+                    <Self as Collection>::Element::redact_raw(raw)
+                }
+            }
+        )+
+    };
+}
+
+impl_from_env_str_collection!(
+    [T: FromEnvStr] Vec<T>,
+    [T: FromEnvStr] VecDeque<T>,
+    [T: FromEnvStr + Eq + Hash, S: BuildHasher + Default] HashSet<T, S>,
+    [T: FromEnvStr + Ord] BTreeSet<T>,
+    [T: FromEnvStr, const N: usize] [T; N],
+);
+
+/// a `Vec`, `VecDeque`, `HashSet`, `BTreeSet` or array with its own separator, e.g.
+/// `Separated<Vec<String>, ';'>`; `' '` splits on any whitespace and `'\n'` on lines.
+/// set `SKIP_EMPTY` to drop empty elements such as the one after a trailing separator. unlike
+/// the bare collections, an element wrapped in `"` or `'` may contain the separator
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Separated<C, const SEP: char = ',', const SKIP_EMPTY: bool = false>(pub C);
+
+impl<C, const SEP: char, const SKIP_EMPTY: bool> Separated<C, SEP, SKIP_EMPTY> {
+    pub fn into_inner(self) -> C {
+        self.0
+    }
+}
+
+impl<C, const SEP: char, const SKIP_EMPTY: bool> std::ops::Deref for Separated<C, SEP, SKIP_EMPTY> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.0
+    }
+}
+
+impl<C: Collection, const SEP: char, const SKIP_EMPTY: bool> FromEnvStr for Separated<C, SEP, SKIP_EMPTY> {
     type Err = VecParseError;
 
     fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
-        if value.is_empty() {
-            return Ok(Vec::new());
-        }
+        parse_collection(value, SEP, SKIP_EMPTY, true).map(Self)
+    }
+
+    fn type_name() -> &'static str {
+        C::TYPE_NAME
+    }
+
+    fn is_sensitive() -> bool {
+        C::Element::is_sensitive()
+    }
+
+    fn redact_raw(raw: &str) -> String {
+        C::Element::redact_raw(raw)
+    }
+}
+
+/// any `FromStr` type, e.g. `Parsed<uuid::Uuid>` or `Parsed<tracing::Level>`; errors name the
+/// type with `std::any::type_name`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Parsed<T>(pub T);
+
+impl<T> Parsed<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Parsed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// the message of a `FromStr` error, which needn't implement `std::error::Error`
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct ParsedError {
+    message: String,
+}
+
+impl std::fmt::Display for ParsedError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ParsedError {}
+
+impl<T> FromEnvStr for Parsed<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    type Err = ParsedError;
+
+    fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
         value
-            .split(',')
-            .enumerate()
-            .map(|(index, element)| T::from_env_str(element.trim()).map_err(|source| VecParseError { index, source: Box::new(source) }))
-            .collect()
+            .parse()
+            .map(Self)
+            .map_err(|err: T::Err| ParsedError { message: err.to_string() })
+    }
+
+    fn type_name() -> &'static str {
+        std::any::type_name::<T>()
+    }
+}
+
+/// which half of a `key=value` pair failed to parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapPart {
+    Key,
+    Value,
+}
+
+#[derive(Debug)]
+enum MapProblem {
+    MissingSeparator(char),
+    Invalid(MapPart, Box<dyn std::error::Error + Send + Sync>),
+    DuplicateKey(String),
+}
+
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct MapParseError {
+    index: usize,
+    problem: MapProblem,
+}
+
+impl MapParseError {
+    /// position of the offending pair, counting from 0
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// whether the key or the value failed to parse; `None` for malformed pairs and duplicate keys
+    pub fn part(&self) -> Option<MapPart> {
+        match self.problem {
+            MapProblem::Invalid(part, _) => Some(part),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for MapParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.problem {
+            MapProblem::MissingSeparator(separator) => write!(formatter, "pair {}: expected key{separator}value", self.index),
+            MapProblem::Invalid(MapPart::Key, source) => write!(formatter, "pair {}: invalid key: {source}", self.index),
+            MapProblem::Invalid(MapPart::Value, source) => write!(formatter, "pair {}: invalid value: {source}", self.index),
+            MapProblem::DuplicateKey(key) => write!(formatter, "pair {}: duplicate key '{key}'", self.index),
+        }
+    }
+}
+
+impl std::error::Error for MapParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.problem {
+            MapProblem::Invalid(_, source) => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+/// parse `k=v,k2=v2`, handing each pair to `insert`, which returns false for a duplicate key
+fn parse_pairs<K: FromEnvStr, V: FromEnvStr>(value: &str, pair_separator: char, key_separator: char, mut insert: impl FnMut(K, V) -> bool) -> std::result::Result<(), MapParseError> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    for (index, pair) in value.split(pair_separator).enumerate() {
+        let error = |problem| MapParseError { index, problem };
+        let (raw_key, raw_value) = pair
+            .split_once(key_separator)
+            .ok_or_else(|| error(MapProblem::MissingSeparator(key_separator)))?;
+        let (raw_key, raw_value) = (raw_key.trim(), raw_value.trim());
+        let key = K::from_env_str(raw_key).map_err(|source| error(MapProblem::Invalid(MapPart::Key, Box::new(source))))?;
+        let parsed = V::from_env_str(raw_value).map_err(|source| error(MapProblem::Invalid(MapPart::Value, Box::new(source))))?;
+        if !insert(key, parsed) {
+            return Err(error(MapProblem::DuplicateKey(K::redact_raw(raw_key))));
+        }
+    }
+    Ok(())
+}
+
+/// `key=value` pairs separated by commas, e.g. `team=core,tier=web`
+impl<K, V, S> FromEnvStr for HashMap<K, V, S>
+where
+    K: FromEnvStr + Eq + Hash,
+    V: FromEnvStr,
+    S: BuildHasher + Default,
+{
+    type Err = MapParseError;
+
+    fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        SeparatedMap::<Self, ',', '='>::from_env_str(value).map(SeparatedMap::into_inner)
+    }
+
+    fn type_name() -> &'static str {
+        "HashMap"
+    }
+
+    fn is_sensitive() -> bool {
+        K::is_sensitive() || V::is_sensitive()
+    }
+
+    fn redact_raw(raw: &str) -> String {
+        V::redact_raw(raw)
+    }
+}
+
+/// `key=value` pairs separated by commas, e.g. `team=core,tier=web`
+impl<K: FromEnvStr + Ord, V: FromEnvStr> FromEnvStr for BTreeMap<K, V> {
+    type Err = MapParseError;
+
+    fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        SeparatedMap::<Self, ',', '='>::from_env_str(value).map(SeparatedMap::into_inner)
+    }
+
+    fn type_name() -> &'static str {
+        "BTreeMap"
+    }
+
+    fn is_sensitive() -> bool {
+        K::is_sensitive() || V::is_sensitive()
+    }
+
+    fn redact_raw(raw: &str) -> String {
+        V::redact_raw(raw)
+    }
+}
+
+/// a `HashMap` or `BTreeMap` with its own separators, e.g. `SeparatedMap<HashMap<String, String>, ';', ':'>`
+/// for `Accept:text/html;X-Trace:1`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeparatedMap<M, const PAIR: char, const KEY: char>(pub M);
+
+impl<M, const PAIR: char, const KEY: char> SeparatedMap<M, PAIR, KEY> {
+    pub fn into_inner(self) -> M {
+        self.0
+    }
+}
+
+impl<M, const PAIR: char, const KEY: char> std::ops::Deref for SeparatedMap<M, PAIR, KEY> {
+    type Target = M;
+
+    fn deref(&self) -> &M {
+        &self.0
+    }
+}
+
+impl<K, V, S, const PAIR: char, const KEY: char> FromEnvStr for SeparatedMap<HashMap<K, V, S>, PAIR, KEY>
+where
+    K: FromEnvStr + Eq + Hash,
+    V: FromEnvStr,
+    S: BuildHasher + Default,
+{
+    type Err = MapParseError;
+
+    fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let mut map = HashMap::default();
+        parse_pairs(value, PAIR, KEY, |key, value| map.insert(key, value).is_none())?;
+        Ok(Self(map))
+    }
+
+    fn type_name() -> &'static str {
+        "HashMap"
+    }
+
+    fn is_sensitive() -> bool {
+        K::is_sensitive() || V::is_sensitive()
+    }
+
+    fn redact_raw(raw: &str) -> String {
+        V::redact_raw(raw)
+    }
+}
+
+impl<K: FromEnvStr + Ord, V: FromEnvStr, const PAIR: char, const KEY: char> FromEnvStr for SeparatedMap<BTreeMap<K, V>, PAIR, KEY> {
+    type Err = MapParseError;
+
+    fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let mut map = BTreeMap::new();
+        parse_pairs(value, PAIR, KEY, |key, value| map.insert(key, value).is_none())?;
+        Ok(Self(map))
     }
 
     fn type_name() -> &'static str {
-        "Vec"
+        "BTreeMap"
+    }
+
+    fn is_sensitive() -> bool {
+        K::is_sensitive() || V::is_sensitive()
+    }
+
+    fn redact_raw(raw: &str) -> String {
+        V::redact_raw(raw)
     }
 }
 
@@ -362,6 +1002,68 @@ mod tests {
         assert_eq!(PathBuf::from_env_str("/tmp/foo").ok(), Some(PathBuf::from("/tmp/foo")));
     }
 
+    #[test]
+    fn osstring_infallible() {
+        assert_eq!(OsString::from_env_str("/tmp/foo").ok(), Some(OsString::from("/tmp/foo")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_unicode_only_accepted_by_os_types() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let raw = OsStr::from_bytes(b"/tmp/\xff");
+        assert!(PathBuf::from_env_os(raw).is_some_and(|parsed| parsed.is_ok()));
+        assert!(OsString::from_env_os(raw).is_some_and(|parsed| parsed.is_ok()));
+        assert!(Option::<PathBuf>::from_env_os(raw).is_some());
+        assert!(String::from_env_os(raw).is_none());
+    }
+
+    #[rstest]
+    #[case("0", 0)]
+    #[case("512", 512)]
+    #[case("512B", 512)]
+    #[case("64k", 64_000)]
+    #[case("64KB", 64_000)]
+    #[case("64KiB", 65_536)]
+    #[case("64ki", 65_536)]
+    #[case("512MiB", 536_870_912)]
+    #[case("1.5GB", 1_500_000_000)]
+    #[case("1.5 GiB", 1_610_612_736)]
+    #[case("0.5kib", 512)]
+    #[case(".25M", 250_000)]
+    #[case("1.0009k", 1_000)]
+    #[case("15EiB", 17_293_822_569_102_704_640)]
+    #[case(" 2 tb ", 2_000_000_000_000)]
+    fn byte_size_parsing(#[case] input: &str, #[case] expected: u64) {
+        assert_eq!(ByteSize::from_env_str(input).unwrap_or_else(|err| panic!("{err}")), ByteSize(expected));
+    }
+
+    #[rstest]
+    #[case("", "empty value")]
+    #[case("12XB", "unknown suffix 'XB'")]
+    #[case("-1k", "unknown suffix '-1k'")]
+    #[case("1.2.3k", "invalid number")]
+    #[case("k", "invalid number")]
+    #[case("20EB", "larger than")]
+    #[case("16EiB", "larger than")]
+    #[case("99999999999999999999999999999999999999999", "larger than")]
+    #[case("340282366920938463463374607431768211.999k", "larger than")]
+    fn byte_size_parse_errors(#[case] input: &str, #[case] message: &str) {
+        let err = ByteSize::from_env_str(input)
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"))
+            .to_string();
+        assert!(err.contains(message), "{err}");
+        assert!(err.starts_with(&format!("cannot parse '{input}' as byte size")), "{err}");
+    }
+
+    #[test]
+    fn byte_size_type_name() {
+        assert_eq!(ByteSize::type_name(), "byte size");
+        assert_eq!(u64::from(ByteSize(7)), ByteSize(7).as_u64());
+    }
+
     #[test]
     fn vec_i32_parsing() {
         let result = Vec::<i32>::from_env_str("1,2,3");
@@ -460,6 +1162,169 @@ mod tests {
         assert!(std::error::Error::source(&err).is_some());
     }
 
+    #[rstest]
+    #[case("'oops", vec!["'oops"])]
+    #[case(r#""a",b"#, vec!["\"a\"", "b"])]
+    #[case(r#"say \"hi\", it's"#, vec![r#"say \"hi\""#, "it's"])]
+    fn vec_quotes_are_literal(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let result = Vec::<String>::from_env_str(input).unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn separated_quoted_elements_contain_separator() {
+        let result = Separated::<Vec<String>>::from_env_str(r#""a,b", 'c, d' ,e,"say \"hi\"""#).unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(*result, vec!["a,b", "c, d", "e", "say \"hi\""]);
+    }
+
+    #[test]
+    fn separated_quote_inside_unquoted_element_is_literal() {
+        let result = Separated::<Vec<String>>::from_env_str("it's,fine").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(*result, vec!["it's", "fine"]);
+    }
+
+    #[rstest]
+    #[case(r#"a,"b"#, "element 1: unterminated \" quote")]
+    #[case(r#""a"b,c"#, "element 0: unexpected content after closing quote")]
+    fn separated_quote_errors(#[case] input: &str, #[case] message: &str) {
+        let err = Separated::<Vec<String>>::from_env_str(input)
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert_eq!(err.to_string(), message);
+    }
+
+    #[test]
+    fn separated_custom_separator() {
+        let result = Separated::<Vec<u16>, ';'>::from_env_str("1; 2;3").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(result.into_inner(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn separated_whitespace_splits_on_any_run() {
+        let result = Separated::<Vec<String>, ' '>::from_env_str("  a \t b\n\nc  \"d e\" ").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(*result, vec!["a", "b", "c", "d e"]);
+    }
+
+    #[test]
+    fn separated_newline_keeps_spaces() {
+        let result = Separated::<Vec<String>, '\n', true>::from_env_str("first line\r\nsecond line\n\n").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(*result, vec!["first line", "second line"]);
+    }
+
+    #[test]
+    fn separated_skip_empty_drops_trailing_element() {
+        let result = Separated::<Vec<i32>, ',', true>::from_env_str("1,,2,").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(*result, vec![1, 2]);
+        let quoted = Separated::<Vec<String>, ',', true>::from_env_str(r#"a,"",b,"#).unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(*quoted, vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn separated_skip_empty_keeps_original_index() {
+        let err = Separated::<Vec<i32>, ',', true>::from_env_str("1,,x")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert_eq!(err.index, 2);
+    }
+
+    #[test]
+    fn sets_and_deque() {
+        let hash_set = HashSet::<String>::from_env_str("a,b,a").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(hash_set.len(), 2);
+        let btree_set = BTreeSet::<u8>::from_env_str("3,1,2,1").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(btree_set.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+        let deque = Separated::<VecDeque<u8>, '|'>::from_env_str("1|2").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(deque.front(), Some(&1));
+    }
+
+    #[test]
+    fn array_with_matching_length() {
+        let result = <[u8; 3]>::from_env_str("1,2,3").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(result, [1, 2, 3]);
+    }
+
+    #[rstest]
+    #[case("1,2", "expected 3 elements, got 2")]
+    #[case("1,2,3,4", "expected 3 elements, got 4")]
+    fn array_length_is_checked(#[case] input: &str, #[case] message: &str) {
+        let err = <[u8; 3]>::from_env_str(input)
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert_eq!(err.to_string(), message);
+    }
+
+    #[test]
+    fn collection_type_names() {
+        assert_eq!(HashSet::<u8>::type_name(), "HashSet");
+        assert_eq!(BTreeSet::<u8>::type_name(), "BTreeSet");
+        assert_eq!(VecDeque::<u8>::type_name(), "VecDeque");
+        assert_eq!(<[u8; 2]>::type_name(), "array");
+        assert_eq!(Separated::<Vec<u8>, ';'>::type_name(), "Vec");
+    }
+
+    #[test]
+    fn hash_map_parsing() {
+        let map: HashMap<String, String> = HashMap::from_env_str("team=core, tier = web,url=a=b").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["team"], "core");
+        assert_eq!(map["tier"], "web");
+        assert_eq!(map["url"], "a=b");
+    }
+
+    #[test]
+    fn btree_map_typed_values() {
+        let map: BTreeMap<String, u16> = BTreeMap::from_env_str("a=1,b=2").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), vec![("a".to_owned(), 1), ("b".to_owned(), 2)]);
+    }
+
+    #[test]
+    fn map_empty_string() {
+        assert!(
+            HashMap::<String, String>::from_env_str("")
+                .unwrap_or_else(|err| panic!("{err}"))
+                .is_empty()
+        );
+    }
+
+    #[rstest]
+    #[case("1=1,2=x", 1, Some(MapPart::Value), "pair 1: invalid value")]
+    #[case("1=1,x=2", 1, Some(MapPart::Key), "pair 1: invalid key")]
+    #[case("1=1,2", 1, None, "pair 1: expected key=value")]
+    #[case("1=1,2=2,1=3", 2, None, "pair 2: duplicate key '1'")]
+    fn map_parse_errors(#[case] input: &str, #[case] index: usize, #[case] part: Option<MapPart>, #[case] message: &str) {
+        let err = BTreeMap::<u8, u8>::from_env_str(input)
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert_eq!(err.index(), index);
+        assert_eq!(err.part(), part);
+        assert!(err.to_string().starts_with(message), "{err}");
+    }
+
+    #[test]
+    fn map_parse_error_has_source() {
+        let err = HashMap::<String, u8>::from_env_str("a=x")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn separated_map_custom_separators() {
+        let headers = SeparatedMap::<HashMap<String, String>, ';', ':'>::from_env_str("Accept:text/html,application/json;X-Trace:1").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(headers["Accept"], "text/html,application/json");
+        assert_eq!(headers.into_inner().len(), 2);
+        let err = SeparatedMap::<BTreeMap<String, String>, ';', ':'>::from_env_str("a=b")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert_eq!(err.to_string(), "pair 0: expected key:value");
+    }
+
+    #[test]
+    fn map_type_names() {
+        assert_eq!(HashMap::<String, String>::type_name(), "HashMap");
+        assert_eq!(BTreeMap::<String, String>::type_name(), "BTreeMap");
+    }
+
     #[cfg(feature = "chrono")]
     mod chrono_tests {
         use rstest::rstest;
@@ -551,4 +1416,108 @@ mod tests {
             assert!(err.to_string().contains("NaiveDate"));
         }
     }
+
+    #[derive(Debug, PartialEq, environs_derive::FromEnvStr)]
+    enum LogFormat {
+        Json,
+        #[env(alias = "text", alias = "human")]
+        Pretty,
+        CompactJson,
+        #[env(rename = "s3")]
+        AmazonS3,
+    }
+
+    #[rstest]
+    #[case("json", LogFormat::Json)]
+    #[case("JSON", LogFormat::Json)]
+    #[case("Pretty", LogFormat::Pretty)]
+    #[case("text", LogFormat::Pretty)]
+    #[case("HUMAN", LogFormat::Pretty)]
+    #[case("compact_json", LogFormat::CompactJson)]
+    #[case("CompactJson", LogFormat::CompactJson)]
+    #[case("compactjson", LogFormat::CompactJson)]
+    #[case("S3", LogFormat::AmazonS3)]
+    #[case(" json", LogFormat::Json)]
+    #[case("pretty \t", LogFormat::Pretty)]
+    fn derived_enum_matches_names_and_aliases(#[case] input: &str, #[case] expected: LogFormat) {
+        assert_eq!(LogFormat::from_env_str(input).unwrap_or_else(|err| panic!("{err}")), expected);
+    }
+
+    #[rstest]
+    #[case("xml")]
+    #[case("amazon_s3")]
+    #[case("AmazonS3")]
+    #[case("compact-json")]
+    #[case("js on")]
+    #[case("")]
+    fn derived_enum_lists_variants(#[case] input: &str) {
+        let err = LogFormat::from_env_str(input)
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert_eq!(err.variants(), &["json", "pretty", "compact_json", "s3"]);
+        assert_eq!(err.to_string(), format!("cannot parse '{input}' as LogFormat: expected one of json, pretty, compact_json, s3"));
+    }
+
+    #[test]
+    fn derived_enum_type_name_and_resolution() {
+        assert_eq!(LogFormat::type_name(), "LogFormat");
+        let map = HashMap::from([("LOG_FORMAT", "Text"), ("BAD", "yaml")]);
+        assert_eq!(
+            crate::resolve::resolve_from::<LogFormat>(&map, &["LOG_FORMAT"]).unwrap_or_else(|err| panic!("{err}")),
+            LogFormat::Pretty
+        );
+        let err = crate::resolve::resolve_from::<LogFormat>(&map, &["BAD"])
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"))
+            .to_string();
+        assert!(err.contains("expected LogFormat, got 'yaml'"), "{err}");
+        let formats: Vec<LogFormat> = Vec::from_env_str("json,s3").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(formats, vec![LogFormat::Json, LogFormat::AmazonS3]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Version(u32, u32);
+
+    impl std::str::FromStr for Version {
+        type Err = String;
+
+        fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+            let (major, minor) = value.split_once('.').ok_or_else(|| format!("no '.' in '{value}'"))?;
+            Ok(Self(major.parse().map_err(|_| "bad major".to_owned())?, minor.parse().map_err(|_| "bad minor".to_owned())?))
+        }
+    }
+
+    #[test]
+    fn parsed_bridges_from_str() {
+        assert_eq!(
+            Parsed::<Version>::from_env_str("1.2")
+                .unwrap_or_else(|err| panic!("{err}"))
+                .into_inner(),
+            Version(1, 2)
+        );
+        assert_eq!(*Parsed::<char>::from_env_str("x").unwrap_or_else(|err| panic!("{err}")), 'x');
+        let err = Parsed::<Version>::from_env_str("12")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert_eq!(err.to_string(), "no '.' in '12'");
+    }
+
+    #[test]
+    fn parsed_type_name_and_error() {
+        assert_eq!(Parsed::<Version>::type_name(), std::any::type_name::<Version>());
+        let map = HashMap::from([("VERSION", "x.1")]);
+        let err = crate::resolve::resolve_from::<Parsed<Version>>(&map, &["VERSION"])
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, crate::Error::Parse { expected, ref got, .. } if expected.ends_with("::Version") && got == "x.1"), "{err}");
+        assert!(err.to_string().ends_with("got 'x.1': bad major"), "{err}");
+    }
+
+    #[test]
+    fn parsed_in_collections() {
+        let versions: Vec<Parsed<Version>> = Vec::from_env_str("1.0,2.5").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(versions, vec![Parsed(Version(1, 0)), Parsed(Version(2, 5))]);
+        let maybe: Option<Parsed<std::num::NonZeroU16>> = Option::from_env_str("8").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(maybe.map(|value| value.get()), Some(8));
+    }
 }
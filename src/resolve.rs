@@ -1,72 +1,179 @@
-use std::env;
-
+use crate::empty::get_checked;
 use crate::error::Error;
 use crate::error::Location;
+use crate::normalize::match_keys;
 use crate::parse::FromEnvStr;
+use crate::source::Env;
+use crate::source::Origin;
+use crate::source::Source;
+use crate::suggest::suggest;
+
+/// a value together with the cascade key and source layer that produced it
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolved<T> {
+    pub value: T,
+    /// `None` when no key was set and the type supplied its own value (e.g. `Option<T>`)
+    pub key: Option<String>,
+    pub origin: Origin,
+}
+
+fn conceal<T: FromEnvStr>(err: Error) -> Error {
+    if T::is_sensitive() { err.redacted() } else { err }
+}
+
+/// fill in near-miss keys on a `NotFound`; only done where the error is returned, not where it falls back to a default
+fn with_suggestions(err: Error, source: &dyn Source, keys: &[&str]) -> Error {
+    match err {
+        Error::NotFound { keys: names, location, .. } => Error::NotFound {
+            keys: names,
+            suggestions: suggest(source, keys),
+            location,
+        },
+        other => other,
+    }
+}
+
+fn not_unicode(source: &dyn Source, key: &str) -> Error {
+    Error::NotUnicode {
+        key: source.key_name(key),
+        location: Location::default(),
+    }
+}
 
 pub fn resolve<T: FromEnvStr>(keys: &[&str]) -> crate::error::Result<T> {
+    resolve_from(&Env, keys)
+}
+
+pub fn resolve_or<T: FromEnvStr>(keys: &[&str], default: T) -> crate::error::Result<T> {
+    resolve_or_from(&Env, keys, default)
+}
+
+pub fn resolve_or_parse<T: FromEnvStr>(keys: &[&str], default_str: &str) -> crate::error::Result<T> {
+    resolve_or_parse_from(&Env, keys, default_str)
+}
+
+pub fn resolve_or_else<T: FromEnvStr>(keys: &[&str], default_fn: impl FnOnce() -> T) -> crate::error::Result<T> {
+    resolve_or_else_from(&Env, keys, default_fn)
+}
+
+pub fn resolve_with<T, E, F>(keys: &[&str], parse_fn: F) -> crate::error::Result<T>
+where
+    E: std::error::Error + Send + Sync + 'static,
+    F: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    resolve_with_from(&Env, keys, parse_fn)
+}
+
+/// like `resolve`, but ignores case and treats `-`, `.` and `_` alike, so `database-url` answers `DATABASE_URL`
+pub fn resolve_normalized<T: FromEnvStr>(keys: &[&str]) -> crate::error::Result<T> {
+    resolve_normalized_from(&Env, keys)
+}
+
+pub fn resolve_normalized_from<T: FromEnvStr>(source: &dyn Source, keys: &[&str]) -> crate::error::Result<T> {
+    let keys = match_keys(source, keys)?;
+    resolve_from(source, &keys.iter().map(String::as_str).collect::<Vec<_>>())
+}
+
+pub fn resolve_from<T: FromEnvStr>(source: &dyn Source, keys: &[&str]) -> crate::error::Result<T> {
+    resolve_traced_from(source, keys).map(|resolved| resolved.value)
+}
+
+fn find_from<T: FromEnvStr>(source: &dyn Source, keys: &[&str]) -> crate::error::Result<T> {
+    find_traced_from(source, keys).map(|resolved| resolved.value)
+}
+
+pub fn resolve_traced<T: FromEnvStr>(keys: &[&str]) -> crate::error::Result<Resolved<T>> {
+    resolve_traced_from(&Env, keys)
+}
+
+pub fn resolve_traced_from<T: FromEnvStr>(source: &dyn Source, keys: &[&str]) -> crate::error::Result<Resolved<T>> {
+    find_traced_from(source, keys).map_err(|err| with_suggestions(err, source, keys))
+}
+
+fn find_traced_from<T: FromEnvStr>(source: &dyn Source, keys: &[&str]) -> crate::error::Result<Resolved<T>> {
     for key in keys {
-        if let Ok(raw) = env::var(key) {
-            return T::from_env_str(&raw).map_err(|source| Error::Parse {
-                key: (*key).to_owned(),
-                expected: T::type_name(),
-                got: raw,
-                source: Box::new(source),
-                location: Location::default(),
-            });
+        if let Some(raw) = get_checked(source, key)? {
+            return match T::from_env_os(&raw) {
+                Some(Ok(value)) => Ok(Resolved {
+                    value,
+                    key: Some((*key).to_owned()),
+                    origin: source.origin(key),
+                }),
+                Some(Err(err)) => Err(conceal::<T>(Error::Parse {
+                    key: source.key_name(key),
+                    expected: T::type_name(),
+                    got: T::redact_raw(&raw.to_string_lossy()),
+                    source: Box::new(err),
+                    origin: Box::new(source.origin(key)),
+                    location: Location::default(),
+                })),
+                None => Err(not_unicode(source, key)),
+            };
         }
     }
-    T::on_not_found(keys)
+    let names: Vec<String> = keys.iter().map(|key| source.key_name(key)).collect();
+    T::on_not_found(&names.iter().map(String::as_str).collect::<Vec<_>>()).map(|value| Resolved {
+        value,
+        key: None,
+        origin: Origin::default(),
+    })
 }
 
-pub fn resolve_or<T: FromEnvStr>(keys: &[&str], default: T) -> crate::error::Result<T> {
-    match resolve::<T>(keys) {
+pub fn resolve_or_from<T: FromEnvStr>(source: &dyn Source, keys: &[&str], default: T) -> crate::error::Result<T> {
+    match find_from::<T>(source, keys) {
         Ok(val) => Ok(val),
         Err(Error::NotFound { .. }) => Ok(default),
         Err(err) => Err(err),
     }
 }
 
-pub fn resolve_or_parse<T: FromEnvStr>(keys: &[&str], default_str: &str) -> crate::error::Result<T> {
-    match resolve::<T>(keys) {
+pub fn resolve_or_parse_from<T: FromEnvStr>(source: &dyn Source, keys: &[&str], default_str: &str) -> crate::error::Result<T> {
+    match find_from::<T>(source, keys) {
         Ok(val) => Ok(val),
-        Err(Error::NotFound { .. }) => T::from_env_str(default_str).map_err(|source| Error::Parse {
-            key: "<default>".to_owned(),
-            expected: T::type_name(),
-            got: default_str.to_owned(),
-            source: Box::new(source),
-            location: Location::default(),
+        Err(Error::NotFound { .. }) => T::from_env_str(default_str).map_err(|source| {
+            conceal::<T>(Error::Parse {
+                key: "<default>".to_owned(),
+                expected: T::type_name(),
+                got: T::redact_raw(default_str),
+                source: Box::new(source),
+                origin: Box::default(),
+                location: Location::default(),
+            })
         }),
         Err(err) => Err(err),
     }
 }
 
-pub fn resolve_or_else<T: FromEnvStr>(keys: &[&str], default_fn: impl FnOnce() -> T) -> crate::error::Result<T> {
-    match resolve::<T>(keys) {
+pub fn resolve_or_else_from<T: FromEnvStr>(source: &dyn Source, keys: &[&str], default_fn: impl FnOnce() -> T) -> crate::error::Result<T> {
+    match find_from::<T>(source, keys) {
         Ok(val) => Ok(val),
         Err(Error::NotFound { .. }) => Ok(default_fn()),
         Err(err) => Err(err),
     }
 }
 
-pub fn resolve_with<T, E, F>(keys: &[&str], parse_fn: F) -> crate::error::Result<T>
+pub fn resolve_with_from<T, E, F>(source: &dyn Source, keys: &[&str], parse_fn: F) -> crate::error::Result<T>
 where
     E: std::error::Error + Send + Sync + 'static,
     F: FnOnce(&str) -> std::result::Result<T, E>,
 {
     for key in keys {
-        if let Ok(raw) = env::var(key) {
-            return parse_fn(&raw).map_err(|source| Error::Parse {
-                key: (*key).to_owned(),
+        if let Some(raw) = get_checked(source, key)? {
+            let raw = raw.into_string().map_err(|_| not_unicode(source, key))?;
+            return parse_fn(&raw).map_err(|err| Error::Parse {
+                key: source.key_name(key),
                 expected: std::any::type_name::<T>(),
                 got: raw,
-                source: Box::new(source),
+                source: Box::new(err),
+                origin: Box::new(source.origin(key)),
                 location: Location::default(),
             });
         }
     }
     Err(Error::NotFound {
-        keys: keys.join(", "),
+        keys: keys.iter().map(|key| source.key_name(key)).collect::<Vec<_>>().join(", "),
+        suggestions: suggest(source, keys),
         location: Location::default(),
     })
 }
@@ -296,4 +403,179 @@ mod tests {
             assert_eq!(result.ok(), Some(8080));
         });
     }
+
+    #[test]
+    fn from_source_reads_map_not_process_env() {
+        let map = std::collections::HashMap::from([("TEST_FROM_MAP", "42")]);
+        temp_env::with_vars([("TEST_FROM_MAP", Some("7"))], || {
+            let result = resolve_from::<i32>(&map, &["TEST_FROM_MAP"]);
+            assert_eq!(result.ok(), Some(42));
+        });
+    }
+
+    #[test]
+    fn from_source_cascade_falls_through() {
+        let map = std::collections::HashMap::from([("PORT", "3000")]);
+        let result = resolve_from::<u16>(&map, &["APP_PORT", "PORT"]);
+        assert_eq!(result.ok(), Some(3000));
+    }
+
+    #[test]
+    fn from_source_missing_returns_not_found() {
+        let map = std::collections::HashMap::<String, String>::new();
+        let result = resolve_from::<String>(&map, &["PORT"]);
+        assert!(matches!(result, Err(Error::NotFound { .. })));
+    }
+
+    #[test]
+    fn from_source_option_none_when_missing() {
+        let map = std::collections::HashMap::<String, String>::new();
+        let result = resolve_from::<Option<u16>>(&map, &["PORT"]);
+        assert_eq!(result.ok(), Some(None));
+    }
+
+    #[test]
+    fn from_source_defaults() {
+        let map = std::collections::HashMap::<String, String>::new();
+        assert_eq!(resolve_or_from::<u16>(&map, &["PORT"], 80).ok(), Some(80));
+        assert_eq!(resolve_or_parse_from::<u16>(&map, &["PORT"], "81").ok(), Some(81));
+        assert_eq!(resolve_or_else_from::<u16>(&map, &["PORT"], || 82).ok(), Some(82));
+    }
+
+    #[test]
+    fn from_source_resolve_with() {
+        let map = std::collections::HashMap::from([("HOSTS", "a:b")]);
+        let result = resolve_with_from(&map, &["HOSTS"], |raw| -> std::result::Result<Vec<String>, std::convert::Infallible> {
+            Ok(raw.split(':').map(str::to_owned).collect())
+        });
+        assert_eq!(result.ok(), Some(vec!["a".to_owned(), "b".to_owned()]));
+    }
+
+    #[test]
+    fn traced_reports_key_and_layer() {
+        let cli = std::collections::HashMap::from([("PORT", "1")]);
+        let defaults = std::collections::HashMap::from([("APP_PORT", "2")]);
+        let layered = crate::source::Layered::new().layer("cli", &cli).layer("defaults", &defaults);
+        let resolved = resolve_traced_from::<u16>(&layered, &["APP_PORT", "PORT"]).unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(resolved.value, 2);
+        assert_eq!(resolved.key.as_deref(), Some("APP_PORT"));
+        assert_eq!(resolved.origin, Origin::new("defaults"));
+    }
+
+    #[test]
+    fn traced_option_missing_has_no_key() {
+        temp_env::with_vars([("TEST_TRACED_MISS", None::<&str>)], || {
+            let resolved = resolve_traced::<Option<u16>>(&["TEST_TRACED_MISS"]).unwrap_or_else(|err| panic!("{err}"));
+            assert_eq!(resolved.value, None);
+            assert_eq!(resolved.key, None);
+        });
+    }
+
+    #[test]
+    fn parse_error_carries_origin() {
+        let local = std::collections::HashMap::from([("PORT", "banana")]);
+        let layered = crate::source::Layered::new().layer(".env.local", &local);
+        match resolve_from::<u16>(&layered, &["PORT"]) {
+            Err(err @ Error::Parse { .. }) => assert!(err.to_string().contains("PORT (from .env.local)"), "{err}"),
+            other => panic!("expected Parse, got {other:?}"),
+        }
+    }
+
+    #[cfg(unix)]
+    fn non_unicode() -> std::ffi::OsString {
+        use std::os::unix::ffi::OsStrExt;
+        std::ffi::OsStr::from_bytes(b"/data/\xff").to_owned()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_unicode_is_not_skipped() {
+        temp_env::with_vars([("TEST_NON_UTF8_A", Some(non_unicode())), ("TEST_NON_UTF8_B", Some("fallback".into()))], || {
+            let result = resolve::<String>(&["TEST_NON_UTF8_A", "TEST_NON_UTF8_B"]);
+            assert!(matches!(result, Err(Error::NotUnicode { ref key, .. }) if key == "TEST_NON_UTF8_A"), "{result:?}");
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_unicode_not_replaced_by_default() {
+        temp_env::with_vars([("TEST_NON_UTF8_DEF", Some(non_unicode()))], || {
+            let result = resolve_or::<String>(&["TEST_NON_UTF8_DEF"], "default".to_owned());
+            assert!(matches!(result, Err(Error::NotUnicode { .. })));
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_unicode_resolve_with() {
+        temp_env::with_vars([("TEST_NON_UTF8_RW", Some(non_unicode()))], || {
+            let result = resolve_with(&["TEST_NON_UTF8_RW"], |raw| raw.parse::<i32>());
+            assert!(matches!(result, Err(Error::NotUnicode { .. })));
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_unicode_path_resolves() {
+        temp_env::with_vars([("TEST_NON_UTF8_PATH", Some(non_unicode()))], || {
+            let path = resolve::<std::path::PathBuf>(&["TEST_NON_UTF8_PATH"]).unwrap_or_else(|err| panic!("{err}"));
+            assert_eq!(path.as_os_str(), non_unicode());
+            let os = resolve::<Option<std::ffi::OsString>>(&["TEST_NON_UTF8_PATH"]).unwrap_or_else(|err| panic!("{err}"));
+            assert_eq!(os, Some(non_unicode()));
+        });
+    }
+
+    #[test]
+    fn normalized_matches_any_case_and_separator() {
+        let map = std::collections::HashMap::from([("app.port", "3000")]);
+        assert_eq!(resolve_normalized_from::<u16>(&map, &["APP_PORT"]).ok(), Some(3000));
+        let err = resolve_normalized_from::<u16>(&map, &["HTTP_PORT"])
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref keys, .. } if keys == "HTTP_PORT"), "{err}");
+    }
+
+    #[test]
+    fn normalized_reads_process_environment() {
+        temp_env::with_vars([("test_normalized_port", Some("4000"))], || {
+            assert_eq!(resolve_normalized::<u16>(&["TEST_NORMALIZED_PORT"]).ok(), Some(4000));
+        });
+    }
+
+    #[test]
+    fn not_found_suggests_near_misses() {
+        let map = std::collections::HashMap::from([("DATABSE_URL", "x"), ("APP_PORT", "1")]);
+        let err = resolve_from::<String>(&map, &["DATABASE_URL", "DB_URL"])
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref suggestions, .. } if suggestions == &["DATABSE_URL"]), "{err}");
+        assert!(err.to_string().ends_with("did you mean DATABSE_URL?"), "{err}");
+
+        let err = resolve_with_from(&map, &["PORT"], |raw| raw.parse::<u16>())
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref suggestions, .. } if suggestions == &["APP_PORT"]), "{err}");
+    }
+
+    #[test]
+    fn not_found_suggests_from_process_environment() {
+        temp_env::with_vars([("TEST_SUGGEST_DATABSE_URL", Some("x")), ("TEST_SUGGEST_DATABASE_URL", None)], || {
+            let err = resolve::<String>(&["TEST_SUGGEST_DATABASE_URL"])
+                .err()
+                .unwrap_or_else(|| panic!("expected an error"));
+            assert!(err.to_string().contains("did you mean TEST_SUGGEST_DATABSE_URL?"), "{err}");
+        });
+    }
+
+    #[test]
+    fn source_policy_applies_to_free_functions() {
+        let map = std::collections::HashMap::from([("APP_PORT", ""), ("PORT", "3000")]);
+        let unset = crate::empty::WithEmptyAs {
+            source: &map,
+            policy: crate::EmptyAs::Unset,
+        };
+        assert_eq!(resolve_from::<u16>(&unset, &["APP_PORT", "PORT"]).ok(), Some(3000));
+        assert_eq!(resolve_with_from(&unset, &["APP_PORT", "PORT"], |raw| raw.parse::<u16>()).ok(), Some(3000));
+        assert_eq!(resolve_or_from::<Option<u16>>(&unset, &["APP_PORT"], Some(1)).ok(), Some(None));
+    }
 }
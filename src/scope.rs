@@ -0,0 +1,146 @@
+use crate::builder::Var;
+use crate::source::{Env, Source};
+
+/// a key prefix shared by a group of lookups, e.g. every `BILLING_` variable
+pub struct Scope<'a> {
+    prefix: String,
+    source: &'a dyn Source,
+    unprefixed_fallback: bool,
+}
+
+/// a scope over the process environment
+pub fn scope(prefix: impl Into<String>) -> Scope<'static> {
+    Scope::from_source(prefix, &Env)
+}
+
+impl<'a> Scope<'a> {
+    pub fn from_source(prefix: impl Into<String>, source: &'a dyn Source) -> Self {
+        Self {
+            prefix: prefix.into(),
+            source,
+            unprefixed_fallback: false,
+        }
+    }
+
+    /// after every prefixed key, also try the keys without the prefix (`BILLING_PORT`, then `PORT`)
+    pub fn unprefixed_fallback(mut self) -> Self {
+        self.unprefixed_fallback = true;
+        self
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub(crate) fn source(&self) -> &'a dyn Source {
+        self.source
+    }
+
+    pub(crate) fn has_unprefixed_fallback(&self) -> bool {
+        self.unprefixed_fallback
+    }
+
+    /// the full cascade for `keys`
+    pub fn expand(&self, keys: &[&str]) -> Vec<String> {
+        expand_keys(&self.prefix, self.unprefixed_fallback, keys)
+    }
+
+    /// a lookup for `keys` within this scope; chain `Var::or_keys` to add keys outside it
+    #[track_caller]
+    pub fn var(&self, keys: &[&str]) -> Var<'a> {
+        Var::from_owned_keys(self.source, keys.iter().map(|key| (*key).to_owned()).collect()).scope(self)
+    }
+}
+
+/// `keys` with `prefix` in front, then as they are when `unprefixed_fallback` is set
+pub(crate) fn expand_keys<K: AsRef<str>>(prefix: &str, unprefixed_fallback: bool, keys: &[K]) -> Vec<String> {
+    let prefixed = keys.iter().map(|key| format!("{prefix}{}", key.as_ref()));
+    let unprefixed = keys.iter().filter(|_| unprefixed_fallback).map(|key| key.as_ref().to_owned());
+    prefixed.chain(unprefixed).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn var_reads_prefixed_key() {
+        let map = HashMap::from([("BILLING_PORT", "8080"), ("PORT", "1")]);
+        let port: u16 = Scope::from_source("BILLING_", &map)
+            .var(&["PORT"])
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(port, 8080);
+    }
+
+    #[test]
+    fn not_found_lists_expanded_keys() {
+        let map = HashMap::from([("PORT", "1")]);
+        let err = Scope::from_source("BILLING_", &map)
+            .var(&["APP_PORT", "PORT"])
+            .get::<u16>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref keys, .. } if keys == "BILLING_APP_PORT, BILLING_PORT"), "{err}");
+    }
+
+    #[test]
+    fn not_found_suggests_the_unprefixed_key() {
+        let map = HashMap::from([("PORT", "1")]);
+        let err = Scope::from_source("BILLING_", &map)
+            .var(&["PORT"])
+            .get::<u16>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref suggestions, .. } if suggestions == &["PORT"]), "{err}");
+        assert!(err.to_string().ends_with("did you mean PORT?"), "{err}");
+    }
+
+    #[test]
+    fn unprefixed_fallback_comes_after_prefixed_keys() {
+        let empty = HashMap::<&str, &str>::new();
+        let billing = Scope::from_source("BILLING_", &empty).unprefixed_fallback();
+        assert_eq!(billing.expand(&["APP_PORT", "PORT"]), vec!["BILLING_APP_PORT", "BILLING_PORT", "APP_PORT", "PORT"]);
+
+        let map = HashMap::from([("PORT", "1")]);
+        let port: u16 = Scope::from_source("BILLING_", &map)
+            .unprefixed_fallback()
+            .var(&["PORT"])
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(port, 1);
+    }
+
+    #[test]
+    fn or_keys_mixes_in_unprefixed_keys() {
+        let map = HashMap::from([("PORT", "1")]);
+        let err = Scope::from_source("BILLING_", &map)
+            .var(&["PORT"])
+            .or_keys(&["HTTP_PORT"])
+            .get::<u16>()
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, Error::NotFound { ref keys, .. } if keys == "BILLING_PORT, HTTP_PORT"), "{err}");
+        let port: u16 = Scope::from_source("BILLING_", &map)
+            .var(&["PORT"])
+            .or_keys(&["PORT"])
+            .get()
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(port, 1);
+    }
+
+    #[test]
+    fn scope_reads_process_environment() {
+        temp_env::with_vars([("TEST_SCOPE_PORT", Some("9000"))], || {
+            let port: u16 = scope("TEST_SCOPE_")
+                .var(&["PORT"])
+                .default(1u16)
+                .get()
+                .unwrap_or_else(|err| panic!("{err}"));
+            assert_eq!(port, 9000);
+        });
+    }
+}
@@ -0,0 +1,138 @@
+use crate::parse::FromEnvStr;
+
+pub(crate) const MASK: &str = "****";
+
+/// mask a sensitive raw value, keeping only its length
+pub(crate) fn mask(raw: &str) -> String {
+    format!("{MASK} ({} chars)", raw.chars().count())
+}
+
+/// stands in for a parse error whose message could echo a sensitive value
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct RedactedError {
+    expected: &'static str,
+}
+
+impl RedactedError {
+    pub fn new(expected: &'static str) -> Self {
+        Self { expected }
+    }
+}
+
+impl std::fmt::Display for RedactedError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "cannot parse value as {} (details redacted)", self.expected)
+    }
+}
+
+impl std::error::Error for RedactedError {}
+
+/// a value that never shows up in `Debug`, `Display` or parse errors
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::fmt::Debug for Secret<T> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "Secret({MASK})")
+    }
+}
+
+impl<T> std::fmt::Display for Secret<T> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str(MASK)
+    }
+}
+
+impl<T: FromEnvStr> FromEnvStr for Secret<T> {
+    type Err = RedactedError;
+
+    fn from_env_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        T::from_env_str(value).map(Self).map_err(|_| RedactedError::new(T::type_name()))
+    }
+
+    fn type_name() -> &'static str {
+        T::type_name()
+    }
+
+    fn is_sensitive() -> bool {
+        true
+    }
+
+    fn from_env_os(value: &std::ffi::OsStr) -> Option<std::result::Result<Self, Self::Err>> {
+        T::from_env_os(value).map(|parsed| parsed.map(Self).map_err(|_| RedactedError::new(T::type_name())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn debug_and_display_are_masked() {
+        let secret = Secret::new("hunter2".to_owned());
+        assert_eq!(format!("{secret:?}"), "Secret(****)");
+        assert_eq!(secret.to_string(), "****");
+        assert_eq!(secret.expose(), "hunter2");
+    }
+
+    #[test]
+    fn parse_error_does_not_echo_value() {
+        let err = Secret::<bool>::from_env_str("hunter2")
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(!err.to_string().contains("hunter2"));
+        assert!(err.to_string().contains("bool"));
+    }
+
+    #[test]
+    fn mask_keeps_length_only() {
+        assert_eq!(mask("hunter2"), "**** (7 chars)");
+    }
+
+    #[test]
+    fn resolve_redacts_secret_type() {
+        temp_env::with_vars([("TEST_SECRET_BAD", Some("hunter2"))], || {
+            let err = crate::resolve::<Secret<u32>>(&["TEST_SECRET_BAD"])
+                .err()
+                .unwrap_or_else(|| panic!("expected an error"));
+            let msg = err.to_string();
+            assert!(matches!(err, Error::Parse { .. }));
+            assert!(!msg.contains("hunter2"), "{msg}");
+            assert!(msg.contains("**** (7 chars)"), "{msg}");
+        });
+    }
+
+    #[test]
+    fn resolve_secret_value() {
+        temp_env::with_vars([("TEST_SECRET_OK", Some("hunter2"))], || {
+            let secret = crate::resolve::<Secret<String>>(&["TEST_SECRET_OK"]).unwrap_or_else(|err| panic!("{err}"));
+            assert_eq!(secret.into_inner(), "hunter2");
+        });
+    }
+
+    #[test]
+    fn option_secret_is_redacted() {
+        temp_env::with_vars([("TEST_SECRET_OPT", Some("hunter2"))], || {
+            let err = crate::resolve::<Option<Secret<u32>>>(&["TEST_SECRET_OPT"])
+                .err()
+                .unwrap_or_else(|| panic!("expected an error"));
+            assert!(!err.to_string().contains("hunter2"), "{err}");
+        });
+    }
+}
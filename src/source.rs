@@ -0,0 +1,427 @@
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+
+use crate::empty::EmptyAs;
+
+/// somewhere raw values can be looked up by key
+pub trait Source {
+    fn get(&self, key: &str) -> Option<String>;
+
+    /// the raw platform string; sources that can hold non-unicode values override this
+    fn get_os(&self, key: &str) -> Option<OsString> {
+        self.get(key).map(OsString::from)
+    }
+
+    /// where the value for `key` came from; only consulted after a hit
+    fn origin(&self, _key: &str) -> Origin {
+        Origin::default()
+    }
+
+    /// every key this source can answer for, in no particular order; used to scan for
+    /// indexed and nested keys. sources that can't enumerate return nothing
+    fn keys(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// what an empty value means here; the process-wide policy from `set_empty_as` by default
+    fn empty_as(&self) -> EmptyAs {
+        crate::empty::empty_as()
+    }
+
+    /// how `key` is named in errors; a view such as `Prefixed` names the key it actually reads
+    fn key_name(&self, key: &str) -> String {
+        key.to_owned()
+    }
+}
+
+/// where a raw value was found
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Origin {
+    pub layer: Option<String>,
+    pub line: Option<usize>,
+}
+
+impl Origin {
+    pub fn new(layer: impl Into<String>) -> Self {
+        Self {
+            layer: Some(layer.into()),
+            line: None,
+        }
+    }
+
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.layer, self.line) {
+            (Some(layer), Some(line)) => write!(formatter, " (from {layer} line {line})"),
+            (Some(layer), None) => write!(formatter, " (from {layer})"),
+            (None, Some(line)) => write!(formatter, " (from line {line})"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// the process environment
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Env;
+
+impl Source for Env {
+    fn get(&self, key: &str) -> Option<String> {
+        std::env::var(key).ok()
+    }
+
+    fn get_os(&self, key: &str) -> Option<OsString> {
+        std::env::var_os(key)
+    }
+
+    /// keys that aren't valid unicode are left out
+    fn keys(&self) -> Vec<String> {
+        std::env::vars_os().filter_map(|(key, _)| key.into_string().ok()).collect()
+    }
+}
+
+impl<K, V, S> Source for HashMap<K, V, S>
+where
+    K: Borrow<str> + Hash + Eq,
+    V: AsRef<str>,
+    S: BuildHasher,
+{
+    fn get(&self, key: &str) -> Option<String> {
+        HashMap::get(self, key).map(|value| value.as_ref().to_owned())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        HashMap::keys(self).map(|key| key.borrow().to_owned()).collect()
+    }
+}
+
+impl<K, V> Source for BTreeMap<K, V>
+where
+    K: Borrow<str> + Ord,
+    V: AsRef<str>,
+{
+    fn get(&self, key: &str) -> Option<String> {
+        BTreeMap::get(self, key).map(|value| value.as_ref().to_owned())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        BTreeMap::keys(self).map(|key| key.borrow().to_owned()).collect()
+    }
+}
+
+impl<T: Source + ?Sized> Source for &T {
+    fn get(&self, key: &str) -> Option<String> {
+        (**self).get(key)
+    }
+
+    fn get_os(&self, key: &str) -> Option<OsString> {
+        (**self).get_os(key)
+    }
+
+    fn origin(&self, key: &str) -> Origin {
+        (**self).origin(key)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        (**self).keys()
+    }
+
+    fn empty_as(&self) -> EmptyAs {
+        (**self).empty_as()
+    }
+
+    fn key_name(&self, key: &str) -> String {
+        (**self).key_name(key)
+    }
+}
+
+/// a stack of named sources; layers are consulted in the order they were added and the first hit wins.
+/// an empty value doesn't count as a hit in a layer whose policy is `EmptyAs::Unset`
+#[derive(Default)]
+pub struct Layered<'a> {
+    layers: Vec<(String, Box<dyn Source + 'a>)>,
+}
+
+impl<'a> Layered<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add a layer below every layer added so far
+    pub fn layer(mut self, name: impl Into<String>, source: impl Source + 'a) -> Self {
+        self.layers.push((name.into(), Box::new(source)));
+        self
+    }
+
+    fn find(&self, key: &str) -> Option<(&str, &dyn Source, OsString)> {
+        self.layers
+            .iter()
+            .find_map(|(name, source)| crate::empty::get_raw(&**source, key).map(|raw| (name.as_str(), &**source, raw)))
+    }
+}
+
+impl Source for Layered<'_> {
+    fn get(&self, key: &str) -> Option<String> {
+        self.find(key).and_then(|(_, _, raw)| raw.into_string().ok())
+    }
+
+    fn get_os(&self, key: &str) -> Option<OsString> {
+        self.find(key).map(|(_, _, raw)| raw)
+    }
+
+    fn origin(&self, key: &str) -> Origin {
+        match self.find(key) {
+            Some((name, source, _)) => Origin {
+                layer: Some(name.to_owned()),
+                line: source.origin(key).line,
+            },
+            None => Origin::default(),
+        }
+    }
+
+    fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.layers.iter().flat_map(|(_, source)| source.keys()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+}
+
+/// a view of `source` where every key is looked up with `prefix` in front of it
+pub struct Prefixed<'a> {
+    prefix: String,
+    source: &'a dyn Source,
+}
+
+impl<'a> Prefixed<'a> {
+    pub fn new(prefix: impl Into<String>, source: &'a dyn Source) -> Self {
+        Self { prefix: prefix.into(), source }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn full_key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+}
+
+impl Source for Prefixed<'_> {
+    fn get(&self, key: &str) -> Option<String> {
+        self.source.get(&self.full_key(key))
+    }
+
+    fn get_os(&self, key: &str) -> Option<OsString> {
+        self.source.get_os(&self.full_key(key))
+    }
+
+    fn origin(&self, key: &str) -> Origin {
+        self.source.origin(&self.full_key(key))
+    }
+
+    /// the keys of `source` that start with the prefix, with the prefix removed
+    fn keys(&self) -> Vec<String> {
+        self.source
+            .keys()
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_owned))
+            .collect()
+    }
+
+    fn empty_as(&self) -> EmptyAs {
+        self.source.empty_as()
+    }
+
+    fn key_name(&self, key: &str) -> String {
+        self.source.key_name(&self.full_key(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_reads_process_environment() {
+        temp_env::with_vars([("TEST_SOURCE_ENV", Some("hello"))], || {
+            assert_eq!(Env.get("TEST_SOURCE_ENV"), Some("hello".to_owned()));
+        });
+    }
+
+    #[test]
+    fn env_missing_is_none() {
+        temp_env::with_vars([("TEST_SOURCE_ENV_MISS", None::<&str>)], || {
+            assert_eq!(Env.get("TEST_SOURCE_ENV_MISS"), None);
+        });
+    }
+
+    #[test]
+    fn hashmap_of_str() {
+        let map = HashMap::from([("PORT", "8080")]);
+        assert_eq!(Source::get(&map, "PORT"), Some("8080".to_owned()));
+        assert_eq!(Source::get(&map, "HOST"), None);
+    }
+
+    #[test]
+    fn hashmap_of_string() {
+        let map = HashMap::from([("PORT".to_owned(), "8080".to_owned())]);
+        assert_eq!(Source::get(&map, "PORT"), Some("8080".to_owned()));
+    }
+
+    #[test]
+    fn btreemap_lookup() {
+        let map = BTreeMap::from([("PORT", "8080")]);
+        assert_eq!(Source::get(&map, "PORT"), Some("8080".to_owned()));
+    }
+
+    #[test]
+    fn reference_delegates() {
+        fn lookup(source: impl Source) -> Option<String> {
+            source.get("PORT")
+        }
+        let map = HashMap::from([("PORT", "8080")]);
+        assert_eq!(lookup(&map), Some("8080".to_owned()));
+    }
+
+    struct Lined;
+
+    impl Source for Lined {
+        fn get(&self, key: &str) -> Option<String> {
+            (key == "PORT").then(|| "4000".to_owned())
+        }
+
+        fn origin(&self, _key: &str) -> Origin {
+            Origin::default().with_line(4)
+        }
+    }
+
+    #[test]
+    fn layered_first_hit_wins() {
+        let cli = HashMap::from([("PORT", "1")]);
+        let defaults = HashMap::from([("PORT", "2"), ("HOST", "localhost")]);
+        let layered = Layered::new().layer("cli", cli).layer("defaults", defaults);
+        assert_eq!(layered.get("PORT"), Some("1".to_owned()));
+        assert_eq!(layered.get("HOST"), Some("localhost".to_owned()));
+        assert_eq!(layered.get("MISSING"), None);
+    }
+
+    #[test]
+    fn layered_empty_layer_falls_through_under_unset() {
+        let cli = HashMap::from([("PORT", "")]);
+        let env = HashMap::from([("PORT", "80")]);
+        let unset = crate::empty::WithEmptyAs { source: &cli, policy: EmptyAs::Unset };
+        let layered = Layered::new().layer("cli", unset).layer("env", &env);
+        assert_eq!(layered.get("PORT"), Some("80".to_owned()));
+        assert_eq!(layered.origin("PORT"), Origin::new("env"));
+        let port: crate::Result<u16> = crate::Var::from_source(&layered, &["PORT"]).get();
+        assert_eq!(port.ok(), Some(80));
+    }
+
+    #[test]
+    fn layered_empty_layer_shadows_under_value() {
+        let cli = HashMap::from([("PORT", "")]);
+        let env = HashMap::from([("PORT", "80")]);
+        let value = crate::empty::WithEmptyAs { source: &cli, policy: EmptyAs::Value };
+        let layered = Layered::new().layer("cli", value).layer("env", &env);
+        assert_eq!(layered.get("PORT"), Some(String::new()));
+        assert_eq!(layered.origin("PORT"), Origin::new("cli"));
+    }
+
+    #[test]
+    fn layered_origin_names_layer() {
+        let cli = HashMap::from([("PORT", "1")]);
+        let defaults = HashMap::from([("HOST", "localhost")]);
+        let layered = Layered::new().layer("cli", &cli).layer("defaults", &defaults);
+        assert_eq!(layered.origin("PORT"), Origin::new("cli"));
+        assert_eq!(layered.origin("HOST"), Origin::new("defaults"));
+        assert_eq!(layered.origin("MISSING"), Origin::default());
+    }
+
+    #[test]
+    fn layered_origin_keeps_line_from_layer() {
+        let layered = Layered::new().layer(".env.local", Lined);
+        assert_eq!(layered.origin("PORT"), Origin::new(".env.local").with_line(4));
+        assert_eq!(layered.origin("PORT").to_string(), " (from .env.local line 4)");
+    }
+
+    #[test]
+    fn layered_with_process_env() {
+        let defaults = HashMap::from([("TEST_LAYERED_ENV", "default")]);
+        temp_env::with_vars([("TEST_LAYERED_ENV", Some("from_env"))], || {
+            let layered = Layered::new().layer("env", Env).layer("defaults", &defaults);
+            assert_eq!(layered.get("TEST_LAYERED_ENV"), Some("from_env".to_owned()));
+        });
+    }
+
+    #[test]
+    fn default_origin_displays_nothing() {
+        assert_eq!(Origin::default().to_string(), "");
+    }
+
+    #[test]
+    fn map_keys_are_listed() {
+        let map = HashMap::from([("PORT", "1"), ("HOST", "h")]);
+        let mut keys = Source::keys(&map);
+        keys.sort();
+        assert_eq!(keys, vec!["HOST", "PORT"]);
+    }
+
+    #[test]
+    fn env_keys_include_process_environment() {
+        temp_env::with_vars([("TEST_SOURCE_KEYS", Some("1"))], || {
+            assert!(Env.keys().iter().any(|key| key == "TEST_SOURCE_KEYS"));
+        });
+    }
+
+    #[test]
+    fn layered_keys_are_merged() {
+        let cli = HashMap::from([("PORT", "1")]);
+        let defaults = HashMap::from([("PORT", "2"), ("HOST", "localhost")]);
+        let layered = Layered::new().layer("cli", &cli).layer("defaults", &defaults);
+        assert_eq!(layered.keys(), vec!["HOST", "PORT"]);
+    }
+
+    #[test]
+    fn prefixed_looks_up_full_key() {
+        let map = HashMap::from([("BILLING_PORT", "1"), ("PORT", "2")]);
+        let prefixed = Prefixed::new("BILLING_", &map);
+        assert_eq!(prefixed.get("PORT"), Some("1".to_owned()));
+        assert_eq!(prefixed.get("HOST"), None);
+        assert_eq!(prefixed.keys(), vec!["PORT"]);
+    }
+
+    #[test]
+    fn prefixed_errors_name_full_keys() {
+        let map = HashMap::from([("APP_PROT", "1"), ("APP_HOST", "h")]);
+        let prefixed = Prefixed::new("APP_", &map);
+        let err = crate::resolve::resolve_from::<u16>(&prefixed, &["A, B", "PORT"])
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(
+            matches!(err, crate::Error::NotFound { ref keys, ref suggestions, .. } if keys == "APP_A, B, APP_PORT" && suggestions == &["APP_PROT"]),
+            "{err}"
+        );
+        let err = crate::resolve::resolve_from::<u16>(&prefixed, &["HOST"])
+            .err()
+            .unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, crate::Error::Parse { ref key, .. } if key == "APP_HOST"), "{err}");
+    }
+
+    #[test]
+    fn prefixed_origin_uses_full_key() {
+        let layered = Layered::new().layer("defaults", HashMap::from([("DB__PORT", "1")]));
+        let prefixed = Prefixed::new("DB__", &layered);
+        assert_eq!(prefixed.origin("PORT"), Origin::new("defaults"));
+    }
+}
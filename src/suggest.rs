@@ -0,0 +1,121 @@
+use crate::normalize::normalize;
+use crate::source::Source;
+
+/// at most this many names are suggested
+const MAX_SUGGESTIONS: usize = 3;
+
+/// set keys that look like a typo of one of `keys`: another case or separator, a prefix one of
+/// them adds or drops (`APP_PORT` or `PORT` for `BILLING_PORT`), or a small edit distance. closest
+/// first, named as in errors
+pub(crate) fn suggest(source: &dyn Source, keys: &[&str]) -> Vec<String> {
+    let wanted: Vec<String> = keys.iter().map(|key| normalize(key)).collect();
+    let mut scored: Vec<(usize, String)> = source
+        .keys()
+        .into_iter()
+        .filter(|candidate| !keys.contains(&candidate.as_str()))
+        .filter_map(|candidate| {
+            let normalized = normalize(&candidate);
+            let score = wanted.iter().filter_map(|key| score(key, &normalized)).min()?;
+            Some((score, candidate))
+        })
+        .collect();
+    scored.sort();
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| source.key_name(&candidate))
+        .collect()
+}
+
+/// how far `candidate` is from `key`, both normalized; `None` when it's too far to suggest
+fn score(key: &str, candidate: &str) -> Option<usize> {
+    if key == candidate {
+        return Some(0);
+    }
+    let has_prefix = |longer: &str, shorter: &str| {
+        longer
+            .strip_suffix(shorter)
+            .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('_'))
+    };
+    if has_prefix(candidate, key) || has_prefix(key, candidate) {
+        return Some(1);
+    }
+    let limit = (key.chars().count() / 4).clamp(1, 3);
+    let distance = edit_distance(key, candidate);
+    (distance <= limit).then_some(distance)
+}
+
+/// edits between `a` and `b`, counting a swap of neighbouring characters as one
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1).min(row[j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("DATABASE_URL", "DATABASE_URL", 0)]
+    #[case("DATABASE_URL", "DATABSE_URL", 1)]
+    #[case("DATABASE_URL", "DATABAES_URL", 1)]
+    #[case("PORT", "PROT", 1)]
+    #[case("", "ABC", 3)]
+    fn edit_distances(#[case] a: &str, #[case] b: &str, #[case] expected: usize) {
+        assert_eq!(edit_distance(a, b), expected);
+    }
+
+    #[rstest]
+    #[case("DATABSE_URL")]
+    #[case("database_url")]
+    #[case("Database-Url")]
+    #[case("APP_DATABASE_URL")]
+    fn suggests_near_misses(#[case] set: &str) {
+        let map = HashMap::from([(set, "x"), ("HOME", "/root")]);
+        assert_eq!(suggest(&map, &["DATABASE_URL"]), vec![set]);
+    }
+
+    #[rstest]
+    #[case("BILLING_PORT", "PORT")]
+    #[case("DATABASE_URL", "URL")]
+    fn suggests_key_without_the_prefix(#[case] wanted: &str, #[case] set: &str) {
+        let map = HashMap::from([(set, "1"), ("HOME", "/root")]);
+        assert_eq!(suggest(&map, &[wanted]), vec![set]);
+    }
+
+    #[test]
+    fn prefix_needs_a_separator() {
+        let map = HashMap::from([("PORT", "1"), ("APPPORT", "1")]);
+        assert!(suggest(&map, &["SUPPORT"]).is_empty());
+    }
+
+    #[test]
+    fn ignores_unrelated_keys() {
+        let map = HashMap::from([("PATH", "/bin"), ("HOME", "/root"), ("SUPPORT", "x")]);
+        assert!(suggest(&map, &["DATABASE_URL", "PORT"]).is_empty());
+    }
+
+    #[test]
+    fn closest_first_and_capped() {
+        let map = HashMap::from([("PROT", "1"), ("port", "1"), ("APP_PORT", "1"), ("PART", "1"), ("POST", "1")]);
+        assert_eq!(suggest(&map, &["PORT"]), vec!["port", "APP_PORT", "PART"]);
+    }
+}